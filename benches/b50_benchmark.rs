@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use image::ImageOutputFormat;
+use maimai_search_lib::service::asset_cache::AssetCache;
+use maimai_search_lib::service::maimai_best_50::DrawBest;
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+/// # B50 绘制
+///
+/// 与 `DrawBest::draw` 相同的绘制与 PNG 编码流程,不写入文件也不打开图片
+fn b50_benchmark(c: &mut Criterion) {
+    let (sd_best, dx_best) = (common::best_list(35, 11000), common::best_list(15, 11500));
+    let cache = Arc::new(AssetCache::new());
+    c.bench_function("B50绘制", |b| {
+        b.iter(|| {
+            let mut draw_best = DrawBest::new(sd_best.clone(), dx_best.clone(), "BENCHMARK");
+            black_box(draw_best.encode(ImageOutputFormat::Png).unwrap())
+        })
+    })
+    .bench_function("B50绘制(共用资源缓存)", |b| {
+        b.iter(|| {
+            let mut draw_best = DrawBest::new(sd_best.clone(), dx_best.clone(), "BENCHMARK")
+                .with_cache(cache.clone());
+            black_box(draw_best.encode(ImageOutputFormat::Png).unwrap())
+        })
+    });
+}
+
+criterion_group!(benches, b50_benchmark);
+criterion_main!(benches);
@@ -30,6 +30,16 @@ pub fn search_songs_by_id(id: usize) -> Option<Song> {
     MaimaiDB::search_song_by_id(id)
 }
 
+/// 获取本地数据库内的全部歌曲
+pub fn get_all_songs() -> Vec<Song> {
+    let songs = MaimaiDB::search_all_songs();
+    if songs.is_empty() {
+        warn!("本地数据库中没有歌曲,请先运行 update 更新数据");
+        exit(exitcode::OK);
+    }
+    songs
+}
+
 /// 按照名称查询歌曲
 pub fn search_songs_by_title(param: &str, count: usize) -> Vec<Song> {
     let songs = MaimaiDB::search_songs_by_title(param.to_lowercase().as_str(), count);
@@ -112,6 +122,13 @@ pub mod entity {
         pub charter: String,
     }
 
+    impl Chart {
+        /// 理论 DX 分数,每个 Note 最多 3 分
+        pub fn max_dx_score(&self) -> i32 {
+            self.notes.iter().sum::<u32>() as i32 * 3
+        }
+    }
+
     /// 歌曲基本信息
     #[derive(Serialize, Deserialize, Debug, Clone)]
     pub struct BasicInfo {
@@ -155,6 +172,11 @@ pub mod entity {
     }
 
     impl Song {
+        /// 是否为宴会场谱面,宴会场谱面不计入 Rating
+        pub fn is_utage(&self) -> bool {
+            self.basic_info.genre == "宴会場" || self.id >= 100000
+        }
+
         /// 获取 Tantivy 的 schema
         pub fn init_schema() -> Schema {
             let mut schema_builder = Schema::builder();
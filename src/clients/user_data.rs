@@ -1,11 +1,13 @@
 use std::error::Error;
+use std::fs;
+use std::path::Path;
 use std::process::exit;
 
 use log::error;
 use reqwest::blocking;
 use serde_json::json;
 
-use crate::clients::user_data::entity::B50Response;
+use crate::clients::user_data::entity::{B50Response, PlayerData, RecordsResponse};
 use crate::config::consts::PROFILE;
 
 /// 从远程服务器拿指定用户的 b50 数据
@@ -43,6 +45,50 @@ pub fn get_b50_data(username: &str) -> Result<B50Response, Box<dyn Error>> {
     })
 }
 
+/// # 从远程服务器拿用户的全部成绩
+///
+/// 查分器只允许获取自己的全部成绩,所以需要在配置文件中填写成绩导入 Token(`import_token`)
+pub fn get_records_data() -> Result<RecordsResponse, Box<dyn Error>> {
+    let config = &PROFILE.remote_api.maimaidxprober;
+    let token = match &config.import_token {
+        Some(token) => token,
+        None => {
+            error!("获取全部成绩需要在配置文件中填写查分器的成绩导入 Token(import_token)");
+            exit(exitcode::CONFIG);
+        }
+    };
+    let response = blocking::Client::new()
+        .get(&config.records_url)
+        .header("Import-Token", token)
+        .send()?;
+    let status = response.status();
+    Ok(match status.as_u16() {
+        200 => response.json()?,
+        400 | 403 => {
+            error!("成绩导入 Token 无效,请在查分器个人资料页重新生成");
+            exit(exitcode::NOPERM);
+        }
+        _ => {
+            error!("[{}] <-- http 请求错误", status);
+            exit(exitcode::NOHOST);
+        }
+    })
+}
+
+/// # 读取本地保存的成绩文件
+///
+/// 文件内容可以是查分器返回的 B50 数据,也可以是全部成绩,通过 `b50 --save-json` 保存的文件可以直接读取
+pub fn read_player_data(path: &Path) -> Result<PlayerData, Box<dyn Error>> {
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// 将成绩数据保存为 JSON 文件,保存的文件可以使用 `read_player_data` 读取
+pub fn save_player_data(data: &PlayerData, path: &Path) -> Result<(), Box<dyn Error>> {
+    fs::write(path, serde_json::to_string_pretty(data)?)?;
+    Ok(())
+}
+
 pub mod entity {
     use std::cmp::Ordering;
 
@@ -50,8 +96,10 @@ pub mod entity {
     use image::Rgba;
     use serde::{Deserialize, Serialize};
 
+    use crate::config::consts::PROFILE;
+
     /// 查分器返回的数据
-    #[derive(Serialize, Deserialize)]
+    #[derive(Serialize, Deserialize, Clone)]
     pub struct B50Response {
         /// 查分器用户名
         pub username: String,
@@ -69,13 +117,62 @@ pub mod entity {
         pub user_general_data: Option<String>,
     }
 
-    #[derive(Serialize, Deserialize)]
+    /// 查分器返回的全部成绩
+    #[derive(Serialize, Deserialize, Clone)]
+    pub struct RecordsResponse {
+        /// 查分器用户名
+        pub username: String,
+        /// 用户名( Maimai 机台上显示的)
+        pub nickname: String,
+        /// 底分
+        pub rating: i32,
+        /// 用户段位
+        pub additional_rating: i32,
+        /// 同 `B50Response::plate`
+        pub plate: String,
+        /// 全部谱面成绩,没有区分新旧版本
+        pub records: Vec<ChartInfoResponse>,
+    }
+
+    /// # 成绩数据
+    ///
+    /// 查分器的两种返回格式,从文件读取时按照字段自动区分
+    #[derive(Serialize, Deserialize, Clone)]
+    #[serde(untagged)]
+    pub enum PlayerData {
+        /// B50 数据,已经按照新旧版本划分
+        B50(B50Response),
+        /// 全部成绩,需要使用本地数据库划分新旧版本
+        Records(RecordsResponse),
+    }
+
+    impl PlayerData {
+        /// 玩家昵称
+        pub fn nickname(&self) -> &str {
+            match self {
+                PlayerData::B50(resp) => &resp.nickname,
+                PlayerData::Records(resp) => &resp.nickname,
+            }
+        }
+
+        /// # 取出全部谱面成绩
+        ///
+        /// B50 数据只包含 B50 列表中的谱面,全部成绩则包含所有游玩过的谱面
+        pub fn into_charts(self) -> Vec<ChartInfoResponse> {
+            match self {
+                PlayerData::B50(resp) => resp.charts.sd.into_iter().chain(resp.charts.dx).collect(),
+                PlayerData::Records(resp) => resp.records,
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Clone)]
     pub struct Charts {
         pub dx: Vec<ChartInfoResponse>,
         pub sd: Vec<ChartInfoResponse>,
     }
 
-    #[derive(Serialize, Deserialize)]
+    #[derive(Serialize, Deserialize, Clone)]
     pub struct ChartInfoResponse {
         /// 达成率
         pub achievements: f32,
@@ -113,29 +210,232 @@ pub mod entity {
     /// 定数 * MIN(完成率,100.5) /100 * 基础 Rating
     /// ```
     ///
-    /// - 基础 rating 是一组固定值,类似一个跳变函数,直接看代码
+    /// - 基础 rating 是一组固定值,类似一个跳变函数,不同版本的取值见 `RatingRule`
     /// - 当你的准度超过 100.5 就只会按照 100.5 来算 Rating 了,所以打到鸟加就没有分辣
     ///
-    /// 值向下取整
+    /// 值向下取整,使用配置文件中选择的 Rating 规则
     #[inline]
     pub fn compute_ra(ds: f32, achievement: f32) -> i32 {
-        let base_ra = match achievement {
-            a if a < 50.0 => 7.0,
-            a if a < 60.0 => 8.0,
-            a if a < 70.0 => 9.6,
-            a if a < 75.0 => 11.2,
-            a if a < 80.0 => 12.0,
-            a if a < 90.0 => 13.6,
-            a if a < 94.0 => 15.2,
-            a if a < 97.0 => 16.8,
-            a if a < 98.0 => 20.0,
-            a if a < 99.0 => 20.3,
-            a if a < 99.5 => 20.8,
-            a if a < 100.0 => 21.1,
-            a if a < 100.5 => 21.6,
-            _ => 22.4,
-        };
-        return (ds * f32::min(achievement, 100.5) / 100.0 * base_ra) as i32;
+        RatingRule::current().compute_ra(ds, achievement)
+    }
+
+    /// # 反查达成率
+    ///
+    /// 计算在指定定数下,单曲 Rating 达到 `target` 所需的最低达成率(精确到 0.0001%)
+    ///
+    /// 使用配置文件中选择的 Rating 规则,详见 `RatingRule::min_achievement_for_ra`
+    pub fn min_achievement_for_ra(ds: f32, target: i32) -> Option<f32> {
+        RatingRule::current().min_achievement_for_ra(ds, target)
+    }
+
+    /// DX 分数星级的阈值(占理论值的百分比),依次为一星到五星
+    pub const DX_STAR_THRESHOLDS: [f32; 5] = [85.0, 90.0, 93.0, 95.0, 97.0];
+
+    /// # DX 分数百分比
+    ///
+    /// 理论值为 0 时(本地没有谱面数据)返回 0
+    pub fn dx_percent(dx_score: i32, max_dx_score: i32) -> f32 {
+        match max_dx_score {
+            0 => 0.0,
+            max => dx_score as f32 / max as f32 * 100.0,
+        }
+    }
+
+    /// # DX 分数星级
+    ///
+    /// 按照 DX 分数占理论值的百分比计算,达到 85% 为一星,97% 为五星,不足 85% 为 0
+    pub fn dx_star(dx_score: i32, max_dx_score: i32) -> u8 {
+        let percent = dx_percent(dx_score, max_dx_score);
+        DX_STAR_THRESHOLDS
+            .iter()
+            .filter(|threshold| percent >= **threshold)
+            .count() as u8
+    }
+
+    /// # Rating 规则
+    ///
+    /// 不同的游戏版本使用不同的评级系数、达成率上限与 Best 列表大小,在配置文件的 `rating.rule` 中选择
+    #[derive(
+        ValueEnum,
+        Serialize,
+        Deserialize,
+        strum_macros::Display,
+        PartialEq,
+        Eq,
+        Copy,
+        Clone,
+        Debug,
+        Default,
+    )]
+    #[serde(rename_all = "lowercase")]
+    pub enum RatingRule {
+        /// maimai でらっくす ~ でらっくす PLUS,B25 + B15,SSS+ 系数为 15.0
+        #[strum(serialize = "dx")]
+        Dx,
+        /// でらっくす Splash 之后的规则,B35 + B15,SSS+ 系数为 22.4
+        #[default]
+        #[strum(serialize = "splash")]
+        Splash,
+        /// PRiSM 之后的规则,在 Splash 的基础上细分了阈值边缘的系数,AP 额外加 1 分
+        #[strum(serialize = "prism")]
+        Prism,
+    }
+
+    impl RatingRule {
+        /// 配置文件中选择的 Rating 规则
+        pub fn current() -> RatingRule {
+            PROFILE.rating.rule
+        }
+
+        /// # 评级系数表
+        ///
+        /// `(达成率下限, 基础 Rating)`,按照达成率从高到低排列,最后一项的下限必须为 0
+        fn coefficients(&self) -> &'static [(f32, f32)] {
+            match self {
+                RatingRule::Dx => &[
+                    (100.5, 15.0),
+                    (100.0, 13.5),
+                    (99.5, 13.2),
+                    (99.0, 13.0),
+                    (98.0, 12.7),
+                    (97.0, 12.5),
+                    (94.0, 10.5),
+                    (90.0, 9.5),
+                    (80.0, 8.5),
+                    (75.0, 7.5),
+                    (70.0, 7.0),
+                    (60.0, 6.0),
+                    (50.0, 5.0),
+                    (0.0, 0.0),
+                ],
+                RatingRule::Splash => &[
+                    (100.5, 22.4),
+                    (100.0, 21.6),
+                    (99.5, 21.1),
+                    (99.0, 20.8),
+                    (98.0, 20.3),
+                    (97.0, 20.0),
+                    (94.0, 16.8),
+                    (90.0, 15.2),
+                    (80.0, 13.6),
+                    (75.0, 12.0),
+                    (70.0, 11.2),
+                    (60.0, 9.6),
+                    (50.0, 8.0),
+                    (0.0, 7.0),
+                ],
+                RatingRule::Prism => &[
+                    (100.5, 22.4),
+                    (100.4999, 22.2),
+                    (100.0, 21.6),
+                    (99.9999, 21.4),
+                    (99.5, 21.1),
+                    (99.0, 20.8),
+                    (98.9999, 20.6),
+                    (98.0, 20.3),
+                    (97.0, 20.0),
+                    (96.9999, 17.6),
+                    (94.0, 16.8),
+                    (90.0, 15.2),
+                    (80.0, 13.6),
+                    (79.9999, 12.8),
+                    (75.0, 12.0),
+                    (70.0, 11.2),
+                    (60.0, 9.6),
+                    (50.0, 8.0),
+                    (40.0, 6.4),
+                    (30.0, 4.8),
+                    (20.0, 3.2),
+                    (10.0, 1.6),
+                    (0.0, 0.0),
+                ],
+            }
+        }
+
+        /// 计入 Rating 的达成率上限
+        pub fn max_achievement(&self) -> f32 {
+            100.5
+        }
+
+        /// AP / AP+ 额外获得的 Rating
+        pub fn ap_bonus(&self) -> i32 {
+            match self {
+                RatingRule::Prism => 1,
+                _ => 0,
+            }
+        }
+
+        /// Best 列表大小,返回 `(旧版本谱面数量, 当前版本谱面数量)`
+        pub fn best_sizes(&self) -> (usize, usize) {
+            match self {
+                RatingRule::Dx => (25, 15),
+                _ => (35, 15),
+            }
+        }
+
+        /// # 计算单曲 Rating
+        ///
+        /// 定数、达成率与系数都换算成整数后再相乘,避免 `as i32` 截断浮点误差导致少算 1 分
+        pub fn compute_ra(&self, ds: f32, achievement: f32) -> i32 {
+            let achievement = f32::min(achievement, self.max_achievement());
+            let base_ra = self
+                .coefficients()
+                .iter()
+                .find(|(threshold, _)| achievement >= *threshold)
+                .map(|(_, base_ra)| *base_ra)
+                .unwrap_or(0.0);
+            let ds = (ds as f64 * 10.0).round() as i64;
+            let achievement = (achievement as f64 * 10000.0).round() as i64;
+            let base_ra = (base_ra as f64 * 10.0).round() as i64;
+            (ds * achievement * base_ra / 100_000_000) as i32
+        }
+
+        /// 计算谱面的 Rating,包含 AP 奖励
+        pub fn chart_ra(&self, chart: &ChartInfoResponse) -> i32 {
+            self.ra_with_fc(chart.ds, chart.achievements, &chart.fc)
+        }
+
+        /// 按照 `fc` 状态计算指定达成率的 Rating,AP/AP+ 时包含 AP 奖励
+        pub fn ra_with_fc(&self, ds: f32, achievement: f32, fc: &str) -> i32 {
+            let bonus = match fc {
+                "ap" | "app" => self.ap_bonus(),
+                _ => 0,
+            };
+            self.compute_ra(ds, achievement) + bonus
+        }
+
+        /// # 反查达成率
+        ///
+        /// Rating 随达成率单调不减,所以直接在 `0 ~ 达成率上限` 之间二分查找,达不到时返回 `None`
+        pub fn min_achievement_for_ra(&self, ds: f32, target: i32) -> Option<f32> {
+            let to_achievement = |step: u32| step as f32 / 10000.0;
+            let (mut low, mut high) = (0u32, (self.max_achievement() * 10000.0) as u32);
+            if self.compute_ra(ds, to_achievement(high)) < target {
+                return None;
+            }
+            while low < high {
+                let mid = (low + high) / 2;
+                match self.compute_ra(ds, to_achievement(mid)) >= target {
+                    true => high = mid,
+                    false => low = mid + 1,
+                }
+            }
+            Some(to_achievement(low))
+        }
+
+        /// # Rating 颜色等级
+        ///
+        /// 对应 `UI_CMN_DXRating_S_01.png` ~ `UI_CMN_DXRating_S_10.png`,旧版本的 Rating 上限较低,颜色阈值也更低
+        pub fn rating_color(&self, rating: u32) -> u32 {
+            let thresholds: &[u32] = match self {
+                RatingRule::Dx => &[1000, 2000, 3000, 4000, 5000, 6000, 7000, 8000, 8500],
+                _ => &[1000, 2000, 4000, 7000, 10000, 12000, 13000, 14500, 15000],
+            };
+            1 + thresholds
+                .iter()
+                .filter(|threshold| rating >= **threshold)
+                .count() as u32
+        }
     }
 
     #[derive(
@@ -149,6 +449,7 @@ pub mod entity {
         PartialEq,
         Copy,
         Clone,
+        Hash,
     )]
     pub enum LevelLabel {
         #[strum(serialize = "BASIC")]
@@ -165,6 +466,18 @@ pub mod entity {
     }
 
     impl LevelLabel {
+        /// 按照谱面在歌曲中的下标获取难度标签
+        pub fn from_index(index: usize) -> Option<LevelLabel> {
+            match index {
+                0 => Some(LevelLabel::Basic),
+                1 => Some(LevelLabel::Advanced),
+                2 => Some(LevelLabel::Expert),
+                3 => Some(LevelLabel::Master),
+                4 => Some(LevelLabel::ReMaster),
+                _ => None,
+            }
+        }
+
         /// 获取难度等级对应的颜色
         pub fn label_color(&self) -> Rgba<u8> {
             match self {
@@ -177,7 +490,9 @@ pub mod entity {
         }
     }
 
-    #[derive(PartialEq, PartialOrd, Serialize, Deserialize)]
+    #[derive(
+        ValueEnum, PartialEq, PartialOrd, Serialize, Deserialize, strum_macros::Display, Clone, Copy,
+    )]
     #[serde(rename_all = "lowercase")]
     pub enum ChartRate {
         D,
@@ -189,14 +504,70 @@ pub mod entity {
         AA,
         AAA,
         S,
+        #[value(name = "s+")]
+        #[strum(serialize = "S+")]
         SP,
         SS,
+        #[value(name = "ss+")]
+        #[strum(serialize = "SS+")]
         SSP,
         SSS,
+        #[value(name = "sss+")]
+        #[strum(serialize = "SSS+")]
         SSSP,
     }
 
+    /// 推分时关注的评级,从低到高排列
+    pub const PUSH_RATES: [ChartRate; 6] = [
+        ChartRate::S,
+        ChartRate::SP,
+        ChartRate::SS,
+        ChartRate::SSP,
+        ChartRate::SSS,
+        ChartRate::SSSP,
+    ];
+
     impl ChartRate {
+        /// 根据达成率获取对应的评级
+        pub fn from_achievement(achievement: f32) -> ChartRate {
+            match achievement {
+                a if a < 50.0 => ChartRate::D,
+                a if a < 60.0 => ChartRate::C,
+                a if a < 70.0 => ChartRate::B,
+                a if a < 75.0 => ChartRate::BB,
+                a if a < 80.0 => ChartRate::BBB,
+                a if a < 90.0 => ChartRate::A,
+                a if a < 94.0 => ChartRate::AA,
+                a if a < 97.0 => ChartRate::AAA,
+                a if a < 98.0 => ChartRate::S,
+                a if a < 99.0 => ChartRate::SP,
+                a if a < 99.5 => ChartRate::SS,
+                a if a < 100.0 => ChartRate::SSP,
+                a if a < 100.5 => ChartRate::SSS,
+                _ => ChartRate::SSSP,
+            }
+        }
+
+        /// 达到该评级所需的最低达成率
+        pub fn min_achievement(&self) -> f32 {
+            match self {
+                ChartRate::D => 0.0,
+                ChartRate::C => 50.0,
+                ChartRate::B => 60.0,
+                ChartRate::BB => 70.0,
+                ChartRate::BBB => 75.0,
+                ChartRate::A => 80.0,
+                ChartRate::AA => 90.0,
+                ChartRate::AAA => 94.0,
+                ChartRate::S => 97.0,
+                ChartRate::SP => 98.0,
+                ChartRate::SS => 99.0,
+                ChartRate::SSP => 99.5,
+                ChartRate::SSS => 100.0,
+                ChartRate::SSSP => 100.5,
+            }
+        }
+
         pub fn get_file_name(&self) -> String {
             format!(
                 "UI_GAM_Rank_{}.png",
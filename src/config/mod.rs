@@ -1,4 +1,5 @@
 pub mod profiles;
+pub mod theme;
 
 pub mod consts {
     extern crate lazy_static;
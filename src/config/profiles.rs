@@ -6,6 +6,7 @@ use log::{error, info, warn};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::clients::user_data::entity::RatingRule;
 use crate::config::consts::{CONFIG_PATH, PROFILE};
 
 /// 配置文件解析结果
@@ -13,6 +14,9 @@ use crate::config::consts::{CONFIG_PATH, PROFILE};
 pub struct Profile {
     pub remote_api: RemoteAPIConfig,
     pub markdown: MarkdownConfig,
+    /// 旧版本的配置文件里没有这一项,缺省时使用默认值
+    #[serde(default)]
+    pub rating: RatingConfig,
 }
 
 /// 远程配置
@@ -27,6 +31,16 @@ pub struct RemoteAPIConfig {
 pub struct MaimaiDXProberConfig {
     pub data_url: String,
     pub username: Option<String>,
+    /// 获取全部成绩的接口,旧版本的配置文件里没有这一项,缺省时使用默认值
+    #[serde(default = "default_records_url")]
+    pub records_url: String,
+    /// 查分器个人资料页生成的成绩导入 Token,获取全部成绩时需要
+    #[serde(default)]
+    pub import_token: Option<String>,
+}
+
+fn default_records_url() -> String {
+    "https://www.diving-fish.com/api/maimaidxprober/player/records".to_string()
 }
 
 /// markdown 配置
@@ -55,6 +69,13 @@ pub struct RemotePictureConfig {
     pub prefix_url: String,
 }
 
+/// Rating 计算配置
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RatingConfig {
+    /// Rating 规则(dx / splash / prism)
+    pub rule: RatingRule,
+}
+
 impl Profile {
     /// 创建默认配置文件
     ///
@@ -143,6 +164,8 @@ impl Profile {
                     data_url: "https://www.diving-fish.com/api/maimaidxprober/query/player"
                         .to_string(),
                     username: None,
+                    records_url: default_records_url(),
+                    import_token: None,
                 },
             },
             markdown: MarkdownConfig {
@@ -158,6 +181,7 @@ impl Profile {
                     console_picture: false,
                 },
             },
+            rating: RatingConfig::default(),
         }
     }
 }
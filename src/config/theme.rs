@@ -0,0 +1,248 @@
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+
+use image::Rgba;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::clients::user_data::entity::LevelLabel;
+use crate::config::consts::CONFIG_PATH;
+
+/// RGBA 颜色,在主题文件中写作 `[r, g, b, a]`
+pub type Color = [u8; 4];
+
+/// # B50 图片主题
+///
+/// 默认主题文件为配置文件夹下的`theme.yml`,所有字段都可以省略,省略的字段使用默认主题(即原本的样式)
+///
+/// - 图片文件的相对路径基于`resource/mai/pic`目录,也可以填写绝对路径
+/// - 字体文件的相对路径基于`resource`目录
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Theme {
+    /// 背景图片
+    pub background: String,
+    /// 背景图片能容纳的网格行数,超出时向下平铺背景,不足时裁掉多余的部分
+    pub background_rows: usize,
+    /// 左上角的 LOGO
+    pub logo: ThemeImage,
+    pub fonts: ThemeFonts,
+    pub colors: ThemeColors,
+    pub grid: ThemeGrid,
+    pub header: ThemeHeader,
+}
+
+/// 带位置与缩放比例的图片
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ThemeImage {
+    pub file: String,
+    pub x: i64,
+    pub y: i64,
+    pub scale: f32,
+}
+
+/// 图片上的位置
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ThemePosition {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// 字体
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThemeFonts {
+    /// 谱面信息等文字使用的字体
+    pub text: String,
+    /// 用户名使用的字体
+    pub name: String,
+}
+
+/// 颜色
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThemeColors {
+    /// 谱面元素上的文字
+    pub text: Color,
+    /// 用户名
+    pub name: Color,
+    /// Rating 合计文字的描边
+    pub outline: Color,
+    /// 右上角 Generated By 文字
+    pub author: Color,
+    /// 谱面元素的阴影
+    pub item_shadow: Color,
+    /// 谱面封面的亮度,1.0 为不压暗
+    pub cover_brightness: f32,
+    /// 右上角难度小三角的颜色,依次为 BASIC 到 Re:MASTER
+    pub levels: [Color; 5],
+}
+
+/// 谱面网格
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThemeGrid {
+    /// 谱面元素的宽度
+    pub item_width: i32,
+    /// 谱面元素的高度
+    pub item_height: i32,
+    /// 行间距
+    pub vertical_spacing: i32,
+    /// 列间距
+    pub horizontal_spacing: i32,
+    /// 网格顶部的位置
+    pub top: i32,
+    /// 旧版本列表的左边距
+    pub sd_left: i32,
+    /// 新版本列表的左边距(从旧版本列表的最后一列算起)
+    pub dx_left: i32,
+    /// 旧版本列表每行的谱面数量
+    pub sd_columns: usize,
+    /// 新版本列表每行的谱面数量
+    pub dx_columns: usize,
+}
+
+/// 头部各个元素的位置
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ThemeHeader {
+    /// 牌子
+    pub plate: ThemePosition,
+    /// Rating
+    pub rating: ThemePosition,
+    /// 姓名框
+    pub name: ThemePosition,
+    /// 姓名框下面的 Rating 合计
+    pub shougou: ThemePosition,
+    /// 右上角 Generated By
+    pub author: ThemePosition,
+    /// 新版本列表标签
+    pub new_label: ThemePosition,
+    /// 旧版本列表标签
+    pub old_label: ThemePosition,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            background: "UI_TTR_BG_Base_Plus.png".to_string(),
+            background_rows: 5,
+            logo: ThemeImage {
+                file: "UI_CMN_TabTitle_MaimaiTitle_Ver214.png".to_string(),
+                x: 10,
+                y: 10,
+                scale: 0.65,
+            },
+            fonts: ThemeFonts::default(),
+            colors: ThemeColors::default(),
+            grid: ThemeGrid::default(),
+            header: ThemeHeader::default(),
+        }
+    }
+}
+
+impl Default for ThemeFonts {
+    fn default() -> Self {
+        ThemeFonts {
+            text: "adobe_simhei.otf".to_string(),
+            name: "msyh.ttc".to_string(),
+        }
+    }
+}
+
+impl Default for ThemeColors {
+    fn default() -> Self {
+        let levels = [0, 1, 2, 3, 4].map(|index| LevelLabel::from_index(index).unwrap());
+        ThemeColors {
+            text: [255, 255, 255, 255],
+            name: [0, 0, 0, 255],
+            outline: [50, 50, 50, 255],
+            author: [75, 75, 75, 255],
+            item_shadow: [0, 0, 0, 150],
+            cover_brightness: 0.72,
+            levels: levels.map(|level| level.label_color().0),
+        }
+    }
+}
+
+impl Default for ThemeGrid {
+    fn default() -> Self {
+        ThemeGrid {
+            item_width: 131,
+            item_height: 88,
+            vertical_spacing: 8,
+            horizontal_spacing: 7,
+            top: 120,
+            sd_left: 6,
+            dx_left: 75,
+            sd_columns: 7,
+            dx_columns: 3,
+        }
+    }
+}
+
+impl Default for ThemeHeader {
+    fn default() -> Self {
+        let position = |x, y| ThemePosition { x, y };
+        ThemeHeader {
+            plate: position(232, 4),
+            rating: position(240, 8),
+            name: position(240, 40),
+            shougou: position(240, 83),
+            author: position(1224, 19),
+            new_label: position(988, 65),
+            old_label: position(865, 65),
+        }
+    }
+}
+
+impl Theme {
+    /// # 加载主题
+    ///
+    /// - 指定了主题文件时加载指定的文件
+    /// - 没有指定时加载配置文件夹下的`theme.yml`,不存在则使用默认主题
+    /// - 解析失败时产生警告并使用默认主题
+    pub fn load(path: Option<&Path>) -> Theme {
+        let default_path = CONFIG_PATH.join("theme.yml");
+        let path = match path {
+            Some(path) => path,
+            None if default_path.exists() => &default_path,
+            None => return Theme::default(),
+        };
+        fs::read_to_string(path)
+            .map_err(|error| error.to_string())
+            .and_then(|yaml| serde_yaml::from_str(&yaml).map_err(|error| error.to_string()))
+            .unwrap_or_else(|error| {
+                warn!(
+                    "主题文件[{}]解析失败,使用默认主题\n[Cause]: {}",
+                    path.display(),
+                    error
+                );
+                Theme::default()
+            })
+    }
+
+    /// 在配置文件夹内创建默认主题文件
+    pub fn create_default() {
+        let path = CONFIG_PATH.join("theme.yml");
+        let yaml = serde_yaml::to_string(&Theme::default()).unwrap();
+        match fs::write(&path, yaml) {
+            Ok(_) => info!("已成功创建主题文件:{}", path.display()),
+            Err(e) => {
+                error!("无法写入文件{:?}", e);
+                exit(exitcode::IOERR);
+            }
+        }
+    }
+
+    /// 难度对应的颜色
+    pub fn level_color(&self, level_label: LevelLabel) -> Rgba<u8> {
+        Rgba(self.colors.levels[level_label as usize])
+    }
+
+    /// 网格的行高
+    pub fn row_height(&self) -> i32 {
+        self.grid.item_height + self.grid.vertical_spacing
+    }
+}
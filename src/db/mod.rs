@@ -6,7 +6,9 @@ pub(crate) mod database {
     use indicatif::{ProgressBar, ProgressStyle};
     use log::{error, info};
     use tantivy::collector::TopDocs;
-    use tantivy::query::{BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, TermQuery};
+    use tantivy::query::{
+        AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, TermQuery,
+    };
     use tantivy::schema::IndexRecordOption;
     use tantivy::{DocAddress, Index, IndexWriter, Score, Searcher, Term};
     use zhconv::{zhconv, Variant};
@@ -122,6 +124,23 @@ pub(crate) mod database {
             }
         }
 
+        /// 获取数据库内的全部歌曲
+        pub fn search_all_songs() -> Vec<Song> {
+            let searcher = Self::get_searcher(&Self::get_index());
+            let limit = usize::max(searcher.num_docs() as usize, 1);
+            match searcher.search(&AllQuery, &TopDocs::with_limit(limit)) {
+                Ok(top_docs) => top_docs
+                    .into_iter()
+                    .map(|(_, doc)| searcher.doc(doc).unwrap())
+                    .filter_map(|doc| Song::from_document(&doc).ok())
+                    .collect(),
+                Err(error) => {
+                    error!("查询全部歌曲时出现错误\n[Cause]:{:?}", error);
+                    exit(exitcode::DATAERR)
+                }
+            }
+        }
+
         /// 按照 Keyword 字段模糊查询歌曲
         ///
         /// Keyword 字段为 title 字段的转小写模式
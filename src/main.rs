@@ -1,17 +1,45 @@
 extern crate clap;
 
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::process::exit;
 
 use clap::Parser;
-use log::{error, info};
+use image::ImageOutputFormat;
+use log::{error, info, warn};
 
-use crate::command::{MaimaiSearchArgs, MarkdownSubCommands, SubCommands};
+use crate::command::{
+    MaimaiSearchArgs, MarkdownSubCommands, PushSubCommands, RatingSubCommands, StatsSubCommands,
+    SubCommands,
+};
 use maimai_search_lib::clients::song_data;
-use maimai_search_lib::clients::user_data::get_b50_data;
+use maimai_search_lib::clients::user_data::entity::{
+    compute_ra, dx_star, min_achievement_for_ra, B50Response, ChartRate, Charts, PlayerData,
+    RatingRule, RecordsResponse,
+};
+use maimai_search_lib::clients::user_data::{
+    get_b50_data, get_records_data, read_player_data, save_player_data,
+};
+use maimai_search_lib::config::consts::{LAUNCH_PATH, PROFILE};
 use maimai_search_lib::config::profiles::Profile;
-use maimai_search_lib::service::maimai_best_50::{BestList, DrawBest};
+use maimai_search_lib::config::theme::Theme;
+use maimai_search_lib::service::best_export::{BestExport, BestFormat};
+use maimai_search_lib::service::calculator::RatingCalculator;
+use maimai_search_lib::service::chart_index::ChartIndex;
+use maimai_search_lib::service::constant_table::{ConstantFormat, ConstantTable};
+use maimai_search_lib::service::history::{HistoryService, RatingSnapshot};
+use maimai_search_lib::service::judgement::{Judgement, JudgementCalculator, NoteCounts};
+use maimai_search_lib::service::level_sheet::{DrawLevelSheet, LevelFilter};
+use maimai_search_lib::service::maimai_best_50::{BestLayout, BestList, DrawBest};
+use maimai_search_lib::service::planner::RatingPlanner;
+use maimai_search_lib::service::plate::{PlateProgress, PlateTarget};
 use maimai_search_lib::service::printer::PrinterHandler;
+use maimai_search_lib::service::recommend::{ChartFilter, RecommendService};
 use maimai_search_lib::service::resource;
+use maimai_search_lib::service::score_push::ScorePushService;
+use maimai_search_lib::service::simulator::SimulateService;
+use maimai_search_lib::service::song_card::DrawSongCard;
+use maimai_search_lib::service::stats::{LevelStatsReport, StatsFormat};
 fn main() {
     simple_log::init().unwrap();
     let args = MaimaiSearchArgs::parse();
@@ -40,7 +68,11 @@ fn main() {
         // 更新资源文件子命令
         Some(SubCommands::Resource { force }) => resource::update_resource(force),
         // 配置文件管理子命令
-        Some(SubCommands::Config { default }) => {
+        Some(SubCommands::Config { default, theme }) => {
+            if theme {
+                Theme::create_default();
+                exit(exitcode::OK)
+            }
             if default {
                 Profile::create_default()
             }
@@ -85,49 +117,748 @@ fn main() {
             }
         }
 
-        Some(SubCommands::B50 { username }) => {
-            let username = match username {
-                None => {
-                    match Profile::get_username() {
-                        Some(username) => username,
-                        None => {
-                            error!("未指定用户名,请在配置文件中指定用户名或者使用 --username 指定用户名");
-                            exit(exitcode::USAGE)
-                        }
+        Some(SubCommands::B50 {
+            username,
+            local,
+            records,
+            version,
+            rule,
+            layout,
+            sizes,
+            input,
+            save_json,
+            format,
+            output,
+            quality,
+            theme,
+            scale,
+        }) => {
+            // 数据输出到标准输出时,日志不能混在里面
+            if matches!(format, BestFormat::Json | BestFormat::Csv)
+                || output.as_deref() == Some("-")
+            {
+                simple_log::redirect_stderr();
+            }
+            let current_rule = RatingRule::current();
+            let rule = rule.unwrap_or(current_rule);
+            let best_sizes = match (layout, sizes) {
+                (Some(BestLayout::Custom), Some(sizes)) => sizes,
+                (Some(layout), _) => layout.sizes().unwrap_or(rule.best_sizes()),
+                (None, _) => rule.best_sizes(),
+            };
+            let data = load_player_data(input.as_deref(), records, username);
+            if save_json {
+                let (name, suffix) = match &data {
+                    PlayerData::B50(resp) => (&resp.username, "b50"),
+                    PlayerData::Records(resp) => (&resp.username, "records"),
+                };
+                let path = LAUNCH_PATH.join(format!("{}-{}.json", name, suffix));
+                match save_player_data(&data, &path) {
+                    Ok(_) => info!("成绩数据已保存:[{}]", path.display()),
+                    Err(e) => warn!("成绩数据保存失败\n[Cause]:{:?}", e),
+                }
+            }
+            let (plate, additional_rating) = match &data {
+                PlayerData::B50(resp) => (resp.plate.clone(), resp.additional_rating),
+                PlayerData::Records(resp) => (resp.plate.clone(), resp.additional_rating),
+            };
+            let (player, nickname, mut sd_charts, mut dx_charts, full) = match data {
+                PlayerData::B50(resp) => (
+                    resp.username,
+                    resp.nickname,
+                    resp.charts.sd,
+                    resp.charts.dx,
+                    false,
+                ),
+                PlayerData::Records(resp) => {
+                    (resp.username, resp.nickname, resp.records, Vec::new(), true)
+                }
+            };
+            info!("用户[{}]的成绩信息已载入", &nickname);
+            let classify = full || version.is_some();
+            // 重新计算与划分版本必须依赖本地数据,其余情况下本地数据只用于计算 DX 分数星级
+            let index = match local || classify {
+                true => Some(ChartIndex::load()),
+                false => ChartIndex::try_load(),
+            };
+            if let (true, Some(index)) = (local, &index) {
+                let mut diffs = index.recompute(&mut sd_charts, rule);
+                diffs.extend(index.recompute(&mut dx_charts, rule));
+                match diffs.is_empty() {
+                    true => info!("本地数据与查分器一致"),
+                    false => {
+                        warn!("有 {} 张谱面与查分器数据不一致", diffs.len());
+                        PrinterHandler::table_handler(vec![ChartIndex::diff_table(&diffs)], false)
                     }
                 }
-                Some(username) => username,
+            }
+            let (sd_best_list, dx_best_list) = match (classify, &index) {
+                (true, Some(index)) => {
+                    sd_charts.append(&mut dx_charts);
+                    BestList::from_records(sd_charts, index, version.as_deref(), rule, best_sizes)
+                }
+                _ => BestList::from_charts(
+                    Charts {
+                        sd: sd_charts,
+                        dx: dx_charts,
+                    },
+                    rule,
+                    best_sizes,
+                ),
+            };
+            // 从文件读取的是旧数据,按照其他版本、规则或布局计算的结果只是假设,都不计入历史记录
+            if input.is_none()
+                && version.is_none()
+                && rule == current_rule
+                && best_sizes == rule.best_sizes()
+            {
+                HistoryService::save(RatingSnapshot::new(&player, &sd_best_list, &dx_best_list));
+            }
+            let export = || {
+                BestExport::new(
+                    &sd_best_list,
+                    &dx_best_list,
+                    &player,
+                    &nickname,
+                    index.as_ref(),
+                )
+            };
+            match (format, format.image_format(quality)) {
+                (_, Some(image_format)) => {
+                    let mut draw_best = DrawBest::new(sd_best_list, dx_best_list, &nickname)
+                        .with_theme(Theme::load(theme.as_deref()))
+                        .with_scale(scale as f32)
+                        .with_plate(&plate)
+                        .with_dan(additional_rating);
+                    if let Some(index) = &index {
+                        draw_best = draw_best.with_chart_index(index);
+                    }
+                    let bytes = match draw_best.encode(image_format) {
+                        Ok(bytes) => bytes,
+                        Err(e) => {
+                            error!("绘制失败: {}", e);
+                            exit(exitcode::SOFTWARE);
+                        }
+                    };
+                    write_or_open(&bytes, &draw_best.output_path(format.extension()), output);
+                }
+                (BestFormat::Md, _) => PrinterHandler::table_file_handler(
+                    export().tables(),
+                    output.filter(|output| output != "-"),
+                    None,
+                ),
+                (BestFormat::Table, _) => PrinterHandler::table_handler(export().tables(), false),
+                (BestFormat::Json, _) => write_output(
+                    format!("{}\n", export().to_json()).as_bytes(),
+                    output_or_stdout(&output),
+                ),
+                (_, _) => write_output(export().to_csv().as_bytes(), output_or_stdout(&output)),
+            }
+        }
+        // 歌曲信息卡片子命令
+        Some(SubCommands::Card {
+            id,
+            username,
+            records,
+            input,
+            output,
+            theme,
+        }) => {
+            quiet_logs_if_stdout(&output);
+            let song = match song_data::search_songs_by_id(id) {
+                Some(song) => song,
+                None => {
+                    error!("找不到 ID 为 {} 的歌曲", id);
+                    exit(exitcode::DATAERR);
+                }
+            };
+            let mut card = DrawSongCard::new(song).with_theme(Theme::load(theme.as_deref()));
+            // 指定了成绩来源时才绘制玩家成绩
+            if username.is_some() || records || input.is_some() {
+                let data = load_player_data(input.as_deref(), records, username);
+                info!("用户[{}]的成绩信息已载入", data.nickname());
+                card = card.with_scores(data.into_charts());
+            }
+            let bytes = match card.encode(ImageOutputFormat::Png) {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    error!("绘制失败: {}", e);
+                    exit(exitcode::SOFTWARE);
+                }
+            };
+            write_or_open(&bytes, &card.output_path(), output);
+        }
+        // 分数列表子命令
+        Some(SubCommands::Scores {
+            level,
+            ds,
+            username,
+            records,
+            input,
+            output,
+            theme,
+        }) => {
+            quiet_logs_if_stdout(&output);
+            // clap 保证等级与定数区间有且只有一个
+            let filter = match ds {
+                Some((from, to)) => LevelFilter::Ds(from, to),
+                None => LevelFilter::Level(level.unwrap_or_default()),
             };
-            let resp = match get_b50_data(username.as_str()) {
-                Ok(resp) => resp,
+            let data = load_player_records(username, input, records, "其余谱面会显示为未游玩");
+            let nickname = data.nickname().to_string();
+            let sheet =
+                DrawLevelSheet::new(&ChartIndex::load(), filter, data.into_charts(), &nickname)
+                    .with_theme(Theme::load(theme.as_deref()));
+            if sheet.charts().is_empty() {
+                error!("没有符合条件的谱面");
+                exit(exitcode::DATAERR);
+            }
+            let bytes = match sheet.encode(ImageOutputFormat::Png) {
+                Ok(bytes) => bytes,
                 Err(e) => {
-                    error!("获取数据失败: {}", e);
-                    exit(exitcode::NOHOST);
+                    error!("绘制失败: {}", e);
+                    exit(exitcode::SOFTWARE);
+                }
+            };
+            write_or_open(&bytes, &sheet.output_path(), output);
+        }
+        // 定数表子命令
+        Some(SubCommands::Constants {
+            level,
+            ds,
+            format,
+            output,
+            theme,
+        }) => {
+            quiet_logs_if_stdout(&output);
+            // clap 保证等级与定数区间有且只有一个
+            let filter = match ds {
+                Some((from, to)) => LevelFilter::Ds(from, to),
+                None => LevelFilter::Level(level.unwrap_or_default()),
+            };
+            let table = ConstantTable::new(ChartIndex::load().songs(), filter)
+                .with_theme(Theme::load(theme.as_deref()));
+            if table.charts().is_empty() {
+                error!("没有符合条件的谱面");
+                exit(exitcode::DATAERR);
+            }
+            // 本地化封面时以输出文件名决定图片目录
+            let path = match output {
+                Some(output) => output,
+                None => table.output_path(format).to_string_lossy().to_string(),
+            };
+            let picture_output = Some(path.clone()).filter(|path| path != "-");
+            let bytes = match format {
+                ConstantFormat::Png => match table.encode(ImageOutputFormat::Png) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        error!("绘制失败: {}", e);
+                        exit(exitcode::SOFTWARE);
+                    }
+                },
+                ConstantFormat::Md => table.to_markdown(&picture_output).into_bytes(),
+                ConstantFormat::Html => table.to_html(&picture_output).into_bytes(),
+            };
+            write_output(&bytes, &path);
+        }
+        // 统计子命令
+        Some(SubCommands::Stats { command }) => match command {
+            StatsSubCommands::Levels {
+                username,
+                records,
+                input,
+                format,
+                output,
+            } => {
+                if format == StatsFormat::Json {
+                    simple_log::redirect_stderr();
+                }
+                let data =
+                    load_player_records(username, input, records, "统计结果只包含 B50 中的谱面");
+                let nickname = data.nickname().to_string();
+                let report =
+                    LevelStatsReport::new(&ChartIndex::load(), data.into_charts(), &nickname);
+                match format {
+                    StatsFormat::Table => {
+                        PrinterHandler::table_handler(vec![report.table()], false)
+                    }
+                    StatsFormat::Md => PrinterHandler::table_file_handler(
+                        vec![report.table()],
+                        output.filter(|output| output != "-"),
+                        None,
+                    ),
+                    StatsFormat::Json => write_output(
+                        format!("{}\n", report.to_json()).as_bytes(),
+                        output_or_stdout(&output),
+                    ),
+                }
+            }
+        },
+        // Rating 历史记录子命令
+        Some(SubCommands::Rating { command }) => match command {
+            RatingSubCommands::History { username, markdown } => {
+                HistoryService::print_history(&get_username(username), markdown)
+            }
+            RatingSubCommands::Diff {
+                from,
+                to,
+                username,
+                markdown,
+            } => HistoryService::print_diff(&get_username(username), from, to, markdown),
+        },
+        // 推分列表子命令
+        Some(SubCommands::Push {
+            command,
+            username,
+            count,
+            markdown,
+            output,
+            save,
+        }) => match command {
+            None => {
+                let username = get_username(username);
+                let resp = fetch_b50(&username);
+                let (sd_best_list, dx_best_list) = {
+                    let rule = RatingRule::current();
+                    BestList::from_charts(resp.charts, rule, rule.best_sizes())
+                };
+                let songs = song_data::get_all_songs();
+                let items =
+                    ScorePushService::get_push_list(&sd_best_list, &dx_best_list, &songs, count);
+                if save {
+                    ScorePushService::save(&username, &items);
+                }
+                let table_vec = vec![ScorePushService::push_table(&items, false)];
+                match markdown || output.is_some() {
+                    true => PrinterHandler::table_file_handler(table_vec, output, None),
+                    false => PrinterHandler::table_handler(table_vec, false),
+                }
+            }
+            Some(PushSubCommands::List { username, markdown }) => {
+                let items = ScorePushService::load(&get_username(username));
+                PrinterHandler::table_handler(
+                    vec![ScorePushService::push_table(&items, true)],
+                    markdown,
+                );
+            }
+            Some(PushSubCommands::Near {
+                margin,
+                username,
+                records,
+                input,
+                markdown,
+            }) => {
+                let charts = load_player_records(username, input, records, "只会列出 B50 中的谱面")
+                    .into_charts();
+                let items =
+                    ScorePushService::near_threshold(&charts, margin, RatingRule::current());
+                if items.is_empty() {
+                    info!("没有距离下一个评级 {}% 以内的谱面", margin);
+                    exit(exitcode::OK)
+                }
+                PrinterHandler::table_handler(
+                    vec![ScorePushService::near_table(&items, margin)],
+                    markdown,
+                );
+            }
+            Some(PushSubCommands::Done { indexes, username }) => {
+                let items = ScorePushService::toggle_done(&get_username(username), &indexes);
+                PrinterHandler::table_handler(
+                    vec![ScorePushService::push_table(&items, true)],
+                    false,
+                );
+            }
+        },
+        // 牌子进度子命令
+        Some(SubCommands::Plate {
+            name,
+            version,
+            goal,
+            username,
+            records,
+            input,
+            markdown,
+            output,
+            image,
+            theme,
+        }) => {
+            quiet_logs_if_stdout(&output);
+            let target = match (name, goal) {
+                (Some(name), None) => PlateTarget::parse(&name).unwrap_or_else(|e| {
+                    error!("{}", e);
+                    exit(exitcode::USAGE)
+                }),
+                (None, Some(goal)) => PlateTarget::custom(version, goal),
+                (_, _) => {
+                    error!("需要指定牌子名称,或者同时指定 --version 与 --goal");
+                    exit(exitcode::USAGE)
                 }
             };
-            info!("用户[{}]的成绩信息已载入,开始绘制", &resp.nickname);
-            let dx_charts = resp.charts.dx;
-            let mut dx_best_list = BestList::new(15);
-            for chart in dx_charts {
-                dx_best_list.push(chart)
-            }
-            let sd_charts = resp.charts.sd;
-            let mut sd_best_list = BestList::new(35);
-            for chart in sd_charts {
-                sd_best_list.push(chart)
-            }
-            let mut draw_best = DrawBest::new(sd_best_list, dx_best_list, &*resp.nickname);
-            match draw_best.draw() {
-                Ok(_) => {}
+            let data = load_player_records(username, input, records, "进度会偏低");
+            let nickname = data.nickname().to_string();
+            let progress = PlateProgress::new(&ChartIndex::load(), target, data.into_charts());
+            if progress.charts().is_empty() {
+                error!("本地数据库中没有[{}]需要的谱面", progress.target.name);
+                exit(exitcode::DATAERR);
+            }
+            let table_vec = vec![progress.progress_table(), progress.remaining_table()];
+            let to_stdout = output.as_deref() == Some("-");
+            // 输出图片时表格只输出到命令行,--output 用于指定图片路径
+            if !image {
+                match output.filter(|_| !to_stdout) {
+                    Some(output) => {
+                        PrinterHandler::table_file_handler(table_vec, Some(output), None)
+                    }
+                    None => PrinterHandler::table_handler(table_vec, markdown || to_stdout),
+                }
+                return;
+            }
+            // 图片输出到标准输出时不打印表格,避免混入图片数据
+            if !to_stdout {
+                PrinterHandler::table_handler(table_vec, markdown);
+            }
+            let sheet = progress
+                .into_sheet(&nickname)
+                .with_theme(Theme::load(theme.as_deref()));
+            let bytes = match sheet.encode(ImageOutputFormat::Png) {
+                Ok(bytes) => bytes,
                 Err(e) => {
                     error!("绘制失败: {}", e);
                     exit(exitcode::SOFTWARE);
                 }
+            };
+            write_or_open(&bytes, &sheet.output_path(), output);
+        }
+        // 未游玩谱面推荐子命令
+        Some(SubCommands::Recommend {
+            rate,
+            level,
+            ds,
+            genre,
+            version,
+            username,
+            records,
+            input,
+            count,
+            markdown,
+        }) => {
+            let data =
+                load_player_records(username, input, records, "B50 以外的谱面都会视为未游玩");
+            let charts = data.into_charts();
+            let index = ChartIndex::load();
+            let rule = RatingRule::current();
+            // 新旧版本与底分都按照本地数据库重新划分
+            let (sd_best_list, dx_best_list) =
+                BestList::from_records(charts.clone(), &index, None, rule, rule.best_sizes());
+            let filter = ChartFilter {
+                level: match (level, ds) {
+                    (_, Some((from, to))) => Some(LevelFilter::Ds(from, to)),
+                    (Some(level), None) => Some(LevelFilter::Level(level)),
+                    (None, None) => None,
+                },
+                genre,
+                version,
+            };
+            let mut items = RecommendService::recommend(
+                &index,
+                (&sd_best_list, &dx_best_list),
+                &charts,
+                &filter,
+                rate,
+            );
+            if items.is_empty() {
+                info!("没有能够超过底分的未游玩谱面");
+                exit(exitcode::OK)
+            }
+            items.truncate(count);
+            PrinterHandler::table_handler(
+                vec![RecommendService::recommend_table(&items, rate)],
+                markdown,
+            );
+        }
+        // Rating 模拟子命令
+        Some(SubCommands::Simulate {
+            chart,
+            username,
+            records,
+            input,
+            markdown,
+        }) => {
+            let data = load_player_records(
+                username,
+                input,
+                records,
+                "新列表中不会出现 B50 以外的已游玩谱面",
+            );
+            let charts = data.into_charts();
+            let index = ChartIndex::load();
+            let rule = RatingRule::current();
+            let mut simulated = charts.clone();
+            if let Err(message) = SimulateService::apply(&mut simulated, &index, &chart) {
+                error!("{}", message);
+                exit(exitcode::DATAERR)
+            }
+            let (sd_before, dx_before) =
+                BestList::from_records(charts, &index, None, rule, rule.best_sizes());
+            let (sd_after, dx_after) =
+                BestList::from_records(simulated, &index, None, rule, rule.best_sizes());
+            PrinterHandler::table_handler(
+                vec![
+                    SimulateService::list_table("B35", &sd_before, &sd_after),
+                    SimulateService::list_table("B15", &dx_before, &dx_after),
+                ],
+                markdown,
+            );
+            let before = sd_before.rating() + dx_before.rating();
+            let after = sd_after.rating() + dx_after.rating();
+            println!("Rating: {} -> {} ({:+})", before, after, after - before);
+        }
+        // 目标 Rating 规划子命令
+        Some(SubCommands::Plan {
+            target,
+            username,
+            max_ds,
+            max_rate,
+            markdown,
+        }) => {
+            let resp = fetch_b50(&get_username(username));
+            let (sd_best_list, dx_best_list) = {
+                let rule = RatingRule::current();
+                BestList::from_charts(resp.charts, rule, rule.best_sizes())
+            };
+            let current = sd_best_list.rating() + dx_best_list.rating();
+            if current >= target {
+                info!("当前 Rating[{}]已经达到目标[{}]", current, target);
+                exit(exitcode::OK)
+            }
+            let plans = RatingPlanner::plan(&sd_best_list, &dx_best_list, target, max_ds, max_rate);
+            if plans.is_empty() {
+                warn!("在当前的定数与评级限制下找不到能达到目标[{}]的方案", target);
+                exit(exitcode::OK)
+            }
+            PrinterHandler::table_handler(RatingPlanner::plan_tables(&plans, current), markdown);
+        }
+        // Rating 计算器子命令
+        Some(SubCommands::Ra {
+            ds,
+            achievement,
+            target,
+            table,
+            markdown,
+        }) => match (ds, achievement, target, table) {
+            (_, _, _, Some((from, to))) => {
+                PrinterHandler::table_handler(vec![RatingCalculator::ra_table(from, to)], markdown)
+            }
+            (Some(ds), _, Some(target), _) => match min_achievement_for_ra(ds, target) {
+                Some(achievement) => println!(
+                    "定数 {} 达到 Rating {} 至少需要 {:.4}% ({})",
+                    ds,
+                    target,
+                    achievement,
+                    ChartRate::from_achievement(achievement)
+                ),
+                None => {
+                    warn!(
+                        "定数 {} 的谱面最高只能获得 {} Rating,无法达到 {}",
+                        ds,
+                        compute_ra(ds, 100.5),
+                        target
+                    );
+                    exit(exitcode::OK)
+                }
+            },
+            (Some(ds), Some(achievement), None, _) => println!(
+                "定数 {} 达成率 {:.4}% ({}) 的单曲 Rating 为 {}",
+                ds,
+                achievement,
+                ChartRate::from_achievement(achievement),
+                compute_ra(ds, achievement)
+            ),
+            _ => error_handler(),
+        },
+        // 判定计算器子命令
+        Some(SubCommands::Judge {
+            id,
+            level,
+            tap,
+            hold,
+            slide,
+            touch,
+            breaks,
+            tolerance,
+            markdown,
+        }) => {
+            let Some(song) = song_data::search_songs_by_id(id) else {
+                error!("找不到 ID 为 {} 的歌曲", id);
+                exit(exitcode::DATAERR)
+            };
+            let Some(notes) = song
+                .charts
+                .get(level as usize)
+                .and_then(NoteCounts::from_chart)
+            else {
+                error!(
+                    "歌曲[{}]{} 没有 {} 难度的 Note 数据",
+                    song.id, song.title, level
+                );
+                exit(exitcode::DATAERR)
+            };
+            let calculator = JudgementCalculator::new(notes);
+            let judgement = Judgement {
+                tap: tap.unwrap_or_default(),
+                hold: hold.unwrap_or_default(),
+                slide: slide.unwrap_or_default(),
+                touch: touch.unwrap_or_default(),
+                breaks: breaks.unwrap_or_default(),
+            };
+            let result = match calculator.score(&judgement) {
+                Ok(result) => result,
+                Err(message) => {
+                    error!("{}", message);
+                    exit(exitcode::DATAERR)
+                }
+            };
+            println!(
+                "[{}]{} {} 达成率 {:.4}% ({}) DX 分数 {} / {} ({}★)",
+                song.id,
+                song.title,
+                level,
+                result.achievements,
+                ChartRate::from_achievement(result.achievements),
+                result.dx_score,
+                result.max_dx_score,
+                dx_star(result.dx_score, result.max_dx_score)
+            );
+            if tolerance {
+                let info = format!("[{}]{} {}", song.id, song.title, level);
+                PrinterHandler::table_handler(vec![calculator.tolerance_table(&info)], markdown);
             }
         }
     }
 }
 
+/// # 写出数据
+///
+/// `output` 为 `-` 时写入标准输出,否则写入对应路径的文件,写入失败时直接退出
+fn write_output(bytes: &[u8], output: &str) {
+    if output == "-" {
+        if let Err(e) = std::io::stdout().write_all(bytes) {
+            error!("写入标准输出失败: {}", e);
+            exit(exitcode::IOERR);
+        }
+        return;
+    }
+    match std::fs::write(output, bytes) {
+        Ok(_) => info!("文件成功写入:[{}]", output),
+        Err(e) => {
+            error!("文件[{}]写入失败: {}", output, e);
+            exit(exitcode::CANTCREAT);
+        }
+    }
+}
+
+/// # 写出图片
+///
+/// 指定了 `output` 时写入该位置(见 `write_output`),否则保存到默认位置 `path` 并使用系统默认程序打开
+fn write_or_open(bytes: &[u8], path: &Path, output: Option<String>) {
+    match output {
+        Some(output) => write_output(bytes, &output),
+        None => {
+            write_output(bytes, &path.to_string_lossy());
+            if let Err(error) = open::that(path) {
+                error!("无法打开文件: {:?}", error);
+            }
+        }
+    }
+}
+
+/// 文本格式没有指定输出位置时输出到标准输出
+fn output_or_stdout(output: &Option<String>) -> &str {
+    output.as_deref().unwrap_or("-")
+}
+
+/// 图片输出到标准输出时把日志改为输出到标准错误,避免日志混入图片数据
+fn quiet_logs_if_stdout(output: &Option<String>) {
+    if output.as_deref() == Some("-") {
+        simple_log::redirect_stderr();
+    }
+}
+
+/// # 获取成绩数据
+///
+/// 指定了文件时从文件读取,否则从查分器获取全部成绩或 B50 数据,失败时直接退出
+fn load_player_data(input: Option<&Path>, records: bool, username: Option<String>) -> PlayerData {
+    match (input, records) {
+        (Some(path), _) => match read_player_data(path) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("读取成绩文件[{}]失败: {}", path.display(), e);
+                exit(exitcode::DATAERR);
+            }
+        },
+        (None, true) => PlayerData::Records(fetch_records()),
+        (None, false) => PlayerData::B50(fetch_b50(&get_username(username))),
+    }
+}
+
+/// # 获取全部成绩
+///
+/// 没有指定用户名或文件、且配置了成绩导入 Token 时默认使用全部成绩,否则与 `load_player_data` 相同;
+/// 只拿到 B50 数据时给出警告,`hint` 说明只有 B50 成绩对当前命令的影响
+fn load_player_records(
+    username: Option<String>,
+    input: Option<PathBuf>,
+    records: bool,
+    hint: &str,
+) -> PlayerData {
+    let token = PROFILE.remote_api.maimaidxprober.import_token.is_some();
+    let records = records || (username.is_none() && input.is_none() && token);
+    let data = load_player_data(input.as_deref(), records, username);
+    if matches!(data, PlayerData::B50(_)) {
+        warn!(
+            "只有 B50 中的成绩,{},填写 import_token 后可以使用全部成绩",
+            hint
+        );
+    }
+    data
+}
+
+/// 从查分器获取配置文件中 Token 对应用户的全部成绩,获取失败时直接退出
+fn fetch_records() -> RecordsResponse {
+    match get_records_data() {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!("获取全部成绩失败: {}", e);
+            exit(exitcode::NOHOST);
+        }
+    }
+}
+
+/// 从查分器获取用户的 B50 数据,获取失败时直接退出
+fn fetch_b50(username: &str) -> B50Response {
+    match get_b50_data(username) {
+        Ok(resp) => resp,
+        Err(e) => {
+            error!("获取数据失败: {}", e);
+            exit(exitcode::NOHOST);
+        }
+    }
+}
+
+/// 获取查分器用户名,未指定时使用配置文件中的用户名
+fn get_username(username: Option<String>) -> String {
+    match username {
+        None => match Profile::get_username() {
+            Some(username) => username,
+            None => {
+                error!("未指定用户名,请在配置文件中指定用户名或者使用 --username 指定用户名");
+                exit(exitcode::USAGE)
+            }
+        },
+        Some(username) => username,
+    }
+}
+
 /// 定义需要处理的字段和对应的字符串表示
 fn error_handler() {
     error!("参数错误,请使用 --help 或者 -h 查看详情");
@@ -135,11 +866,15 @@ fn error_handler() {
 }
 
 mod simple_log {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
     use colored::Colorize;
     use log::{Level, Metadata, Record};
     use log::{LevelFilter, SetLoggerError};
 
     static LOGGER: SimpleLogger = SimpleLogger;
+    /// 为真时所有日志都输出到标准错误
+    static STDERR: AtomicBool = AtomicBool::new(false);
 
     struct SimpleLogger;
 
@@ -155,10 +890,13 @@ mod simple_log {
                         eprintln!("{}{} {}", "error".red().bold(), ":".bold(), args);
                     }
                     Level::Warn => {
-                        println!("{}{} {}", "warning".yellow().bold(), ":".bold(), args);
+                        let message =
+                            format!("{}{} {}", "warning".yellow().bold(), ":".bold(), args);
+                        Self::print(message);
                     }
                     Level::Info => {
-                        println!("{}{} {}", "info".green().bold(), ":".bold(), args);
+                        let message = format!("{}{} {}", "info".green().bold(), ":".bold(), args);
+                        Self::print(message);
                     }
                     _ => {}
                 }
@@ -167,14 +905,40 @@ mod simple_log {
         fn flush(&self) {}
     }
 
+    impl SimpleLogger {
+        fn print(message: String) {
+            match STDERR.load(Ordering::Relaxed) {
+                true => eprintln!("{}", message),
+                false => println!("{}", message),
+            }
+        }
+    }
+
+    /// 将所有日志输出到标准错误,用于标准输出需要保持干净的场景(例如输出 JSON 给其他程序)
+    pub fn redirect_stderr() {
+        STDERR.store(true, Ordering::Relaxed);
+    }
+
     pub fn init() -> Result<(), SetLoggerError> {
         log::set_logger(&LOGGER).map(|()| log::set_max_level(LevelFilter::Info))
     }
 }
 
 mod command {
+    use std::path::PathBuf;
+
     use clap::{Parser, Subcommand};
-    use maimai_search_lib::clients::user_data::entity::LevelLabel;
+    use maimai_search_lib::clients::user_data::entity::{ChartRate, LevelLabel, RatingRule};
+    use maimai_search_lib::service::best_export::BestFormat;
+    use maimai_search_lib::service::calculator::parse_ds_range;
+    use maimai_search_lib::service::constant_table::ConstantFormat;
+    use maimai_search_lib::service::judgement::{
+        parse_break_counts, parse_judge_counts, BreakJudgeCounts, JudgeCounts,
+    };
+    use maimai_search_lib::service::maimai_best_50::{parse_best_sizes, BestLayout};
+    use maimai_search_lib::service::plate::PlateGoal;
+    use maimai_search_lib::service::simulator::{parse_simulated_chart, SimulatedChart};
+    use maimai_search_lib::service::stats::StatsFormat;
 
     /// GitHub Repository : [https://github.com/Anselyuki/maimai-search-rs]
     #[derive(Parser)]
@@ -245,11 +1009,387 @@ mod command {
             /// 在配置文件夹内创建默认配置文件
             #[arg(short, long)]
             default: bool,
+            /// 在配置文件夹内创建默认的 B50 主题文件(theme.yml)
+            #[arg(short, long)]
+            theme: bool,
         },
         /// 生成 B50 图片
         B50 {
             /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
             username: Option<String>,
+            /// 使用本地数据库重新计算定数与 Rating,并列出与查分器不一致的谱面
+            #[arg(short, long)]
+            local: bool,
+            /// 获取全部成绩并使用本地数据库重新划分新旧版本,需要在配置文件中填写 import_token
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 指定当前版本(如 "maimai でらっくす FESTiVAL"),按照歌曲的收录版本划分新旧版本
+            #[arg(long)]
+            version: Option<String>,
+            /// 使用指定的 Rating 规则计算,缺省时使用配置文件中的规则
+            #[arg(long, value_enum)]
+            rule: Option<RatingRule>,
+            /// 图片布局,缺省时使用 Rating 规则对应的布局(DX 规则为 B40,其余为 B50)
+            #[arg(long, value_enum)]
+            layout: Option<BestLayout>,
+            /// 自定义布局的列表大小,格式为`旧版本,新版本`,例如 25,15
+            #[arg(long, value_parser = parse_best_sizes, required_if_eq("layout", "custom"))]
+            sizes: Option<(usize, usize)>,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩),不再请求查分器
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 将查分器返回的成绩数据保存为 JSON 文件,保存在程序所在目录下
+            #[arg(long)]
+            save_json: bool,
+            /// 输出格式,图片默认保存在程序所在目录下,其余格式默认输出在命令行
+            #[arg(short, long, value_enum, default_value_t = BestFormat::Png)]
+            format: BestFormat,
+            /// 输出文件路径,为 - 时输出到标准输出(Markdown 格式与 search 命令的 -o 参数相同)
+            #[arg(short, long)]
+            output: Option<String>,
+            /// 图片质量(1 ~ 100),目前只对 JPEG 生效
+            #[arg(short, long, default_value_t = 90, value_parser = clap::value_parser!(u8).range(1..=100))]
+            quality: u8,
+            /// B50 图片的主题文件,缺省时使用配置文件夹下的 theme.yml
+            #[arg(long, value_name = "THEME_FILE")]
+            theme: Option<PathBuf>,
+            /// 图片缩放倍数(1 ~ 4),用于输出在手机上查看或打印的高清图片
+            #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(1..=4))]
+            scale: u8,
+        },
+        /// 生成歌曲信息卡片图片,如：maimai-search card 834 -u username
+        Card {
+            /// 歌曲 ID
+            id: usize,
+            /// 在卡片上绘制该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 在卡片上绘制全部成绩中的成绩,需要在配置文件中填写 import_token
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 输出文件路径,为 - 时输出到标准输出,缺省时保存在程序所在目录下
+            #[arg(short, long)]
+            output: Option<String>,
+            /// 主题文件,缺省时使用配置文件夹下的 theme.yml
+            #[arg(long, value_name = "THEME_FILE")]
+            theme: Option<PathBuf>,
+        },
+        /// 生成分数列表图片,如：maimai-search scores 13+ 或 maimai-search scores --ds 13.7..13.9
+        Scores {
+            /// 谱面等级,如 13+
+            level: Option<String>,
+            /// 定数区间(包含两端),如 13.7..13.9
+            #[arg(long, value_name = "RANGE", value_parser = parse_ds_range, conflicts_with = "level", required_unless_present = "level")]
+            ds: Option<(f32, f32)>,
+            /// 使用该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用全部成绩,配置文件中填写了 import_token 时默认使用
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 输出文件路径,为 - 时输出到标准输出,缺省时保存在程序所在目录下
+            #[arg(short, long)]
+            output: Option<String>,
+            /// 主题文件,缺省时使用配置文件夹下的 theme.yml
+            #[arg(long, value_name = "THEME_FILE")]
+            theme: Option<PathBuf>,
+        },
+        /// 生成定数表,如：maimai-search constants 13+ 或 maimai-search constants --ds 13.0..13.9 --format html
+        Constants {
+            /// 谱面等级,如 13+
+            level: Option<String>,
+            /// 定数区间(包含两端),如 13.0..13.9
+            #[arg(long, value_name = "RANGE", value_parser = parse_ds_range, conflicts_with = "level", required_unless_present = "level")]
+            ds: Option<(f32, f32)>,
+            /// 输出格式
+            #[arg(short, long, value_enum, default_value_t = ConstantFormat::Png)]
+            format: ConstantFormat,
+            /// 输出文件路径,为 - 时输出到标准输出,缺省时保存在程序所在目录下
+            #[arg(short, long)]
+            output: Option<String>,
+            /// 主题文件,缺省时使用配置文件夹下的 theme.yml
+            #[arg(long, value_name = "THEME_FILE")]
+            theme: Option<PathBuf>,
+        },
+        /// 推分列表,根据 B50 与本地数据库计算推分收益,详情请运行 maimai-search push --help
+        Push {
+            #[command(subcommand)]
+            command: Option<PushSubCommands>,
+            /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
+            username: Option<String>,
+            /// 推分列表的条目数量
+            #[arg(short, long, default_value = "20")]
+            count: usize,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+            /// 指定 markdown 输出的文件名称(路径使用当前程序执行的路径)
+            #[arg(short, long, value_name = "MARKDOWN_FILE_NAME")]
+            output: Option<String>,
+            /// 保存推分列表,之后可以使用 push done 勾选完成的条目
+            #[arg(short, long)]
+            save: bool,
+        },
+        /// 牌子进度,如：maimai-search plate 祭将 或 maimai-search plate --version "maimai でらっくす" --goal ji
+        Plate {
+            /// 牌子名称,第一个字为版本,其余为达成条件(極/将/神/舞舞),如 祭将、舞舞舞
+            name: Option<String>,
+            /// 自定义版本组合,可以指定多个收录版本,需要配合 --goal 使用
+            #[arg(
+                long,
+                conflicts_with = "name",
+                required_unless_present = "name",
+                requires = "goal"
+            )]
+            version: Vec<String>,
+            /// 自定义版本组合的达成条件
+            #[arg(
+                long,
+                value_enum,
+                conflicts_with = "name",
+                required_unless_present = "name"
+            )]
+            goal: Option<PlateGoal>,
+            /// 使用该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用全部成绩,配置文件中填写了 import_token 时默认使用
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+            /// 输出文件路径,输出图片时为图片路径,否则为 markdown 文件路径;为 - 时输出到标准输出
+            #[arg(short, long)]
+            output: Option<String>,
+            /// 将剩余谱面绘制为图片
+            #[arg(long)]
+            image: bool,
+            /// 主题文件,缺省时使用配置文件夹下的 theme.yml
+            #[arg(long, value_name = "THEME_FILE")]
+            theme: Option<PathBuf>,
+        },
+        /// 未游玩谱面推荐,列出以目标评级完成后能超过 B35/B15 底分的谱面,如：maimai-search recommend --level 13+
+        Recommend {
+            /// 目标评级
+            #[arg(long, value_enum, default_value = "sss")]
+            rate: ChartRate,
+            /// 只推荐该等级的谱面,如 13+
+            #[arg(long)]
+            level: Option<String>,
+            /// 只推荐定数区间(包含两端)内的谱面,如 13.0..13.9
+            #[arg(long, value_name = "RANGE", value_parser = parse_ds_range, conflicts_with = "level")]
+            ds: Option<(f32, f32)>,
+            /// 只推荐该分区的谱面,如 "POPS&アニメ"
+            #[arg(long)]
+            genre: Option<String>,
+            /// 只推荐该版本收录的谱面,如 "maimai でらっくす FESTiVAL"
+            #[arg(long)]
+            version: Option<String>,
+            /// 使用该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用全部成绩,配置文件中填写了 import_token 时默认使用
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 推荐的谱面数量
+            #[arg(short, long, default_value = "30")]
+            count: usize,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// Rating 模拟,假设取得某些成绩后重新计算 B35/B15,如：maimai-search simulate --chart 834:master:100.5
+        Simulate {
+            /// 假设的成绩,格式为 歌曲ID:难度:达成率,难度可以是名称或下标(0 ~ 4),可以多次指定
+            #[arg(short, long, value_name = "ID:DIFFICULTY:ACHIEVEMENT", value_parser = parse_simulated_chart, required = true)]
+            chart: Vec<SimulatedChart>,
+            /// 使用该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用全部成绩,配置文件中填写了 import_token 时默认使用
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// 目标 Rating 规划,如：maimai-search plan 15000 --max-ds 14.5 --max-rate sss
+        Plan {
+            /// 目标 Rating
+            target: i32,
+            /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 方案中允许的最高定数
+            #[arg(long, default_value = "15.0")]
+            max_ds: f32,
+            /// 方案中允许的最高评级
+            #[arg(long, value_enum, default_value = "sss+")]
+            max_rate: ChartRate,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// Rating 计算器,如：maimai-search ra 14.6 100.5 或 maimai-search ra 14.6 --target 320
+        Ra {
+            /// 谱面定数
+            ds: Option<f32>,
+            /// 达成率
+            achievement: Option<f32>,
+            /// 目标单曲 Rating,计算达到该 Rating 所需的最低达成率
+            #[arg(short, long, conflicts_with = "achievement")]
+            target: Option<i32>,
+            /// 输出定数区间内各评级的 Rating 表,如：--table 13.0..15.0
+            #[arg(long, value_name = "RANGE", value_parser = parse_ds_range)]
+            table: Option<(f32, f32)>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// 判定计算器,由各种 Note 的判定计算达成率与 DX 分数,如：maimai-search judge 834 master --tap 0,3 --break 2
+        Judge {
+            /// 歌曲 ID
+            id: usize,
+            /// 谱面难度
+            #[arg(value_enum)]
+            level: LevelLabel,
+            /// TAP 的判定,格式为 PERFECT,GREAT,GOOD,MISS,可以省略末尾的项,其余视为 CRITICAL PERFECT
+            #[arg(long, value_name = "COUNTS", value_parser = parse_judge_counts)]
+            tap: Option<JudgeCounts>,
+            /// HOLD 的判定,格式同 --tap
+            #[arg(long, value_name = "COUNTS", value_parser = parse_judge_counts)]
+            hold: Option<JudgeCounts>,
+            /// SLIDE 的判定,格式同 --tap
+            #[arg(long, value_name = "COUNTS", value_parser = parse_judge_counts)]
+            slide: Option<JudgeCounts>,
+            /// TOUCH 的判定,格式同 --tap
+            #[arg(long, value_name = "COUNTS", value_parser = parse_judge_counts)]
+            touch: Option<JudgeCounts>,
+            /// BREAK 的判定,格式为 50落,100落,GREAT,GOOD,MISS(GREAT 按 2000 分计算)或 50落,100落,GREAT2000,GREAT1500,GREAT1250,GOOD,MISS
+            #[arg(long = "break", value_name = "COUNTS", value_parser = parse_break_counts)]
+            breaks: Option<BreakJudgeCounts>,
+            /// 输出 SSS 与 SSS+ 的容错表
+            #[arg(short, long)]
+            tolerance: bool,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// Rating 历史记录,详情请运行 maimai-search rating --help
+        Rating {
+            #[command(subcommand)]
+            command: RatingSubCommands,
+        },
+        /// 成绩统计,详情请运行 maimai-search stats --help
+        Stats {
+            #[command(subcommand)]
+            command: StatsSubCommands,
+        },
+    }
+
+    /// 成绩统计
+    #[derive(Subcommand)]
+    pub enum StatsSubCommands {
+        /// 按照等级统计游玩数量、评级、FC/FS 与平均达成率,如：maimai-search stats levels --format md
+        Levels {
+            /// 使用该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用全部成绩,配置文件中填写了 import_token 时默认使用
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 输出格式
+            #[arg(short, long, value_enum, default_value_t = StatsFormat::Table)]
+            format: StatsFormat,
+            /// 输出文件路径,缺省时输出到命令行
+            #[arg(short, long)]
+            output: Option<String>,
+        },
+    }
+
+    /// 推分列表
+    #[derive(Subcommand)]
+    pub enum PushSubCommands {
+        /// 查看保存的推分列表
+        List {
+            /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// 临近下一个评级的谱面,如：maimai-search push near --margin 0.3
+        Near {
+            /// 与下一个评级(S ~ SSS+)的最大差距,单位为百分点
+            #[arg(long, default_value_t = 0.5)]
+            margin: f32,
+            /// 使用该用户 B50 中的成绩
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用全部成绩,配置文件中填写了 import_token 时默认使用
+            #[arg(short, long, conflicts_with = "username")]
+            records: bool,
+            /// 从本地 JSON 文件读取成绩(B50 数据或全部成绩)
+            #[arg(short, long, conflicts_with_all = ["username", "records"])]
+            input: Option<PathBuf>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// 勾选(或取消勾选)保存的推分条目,如：maimai-search push done 1 3
+        Done {
+            /// 推分条目序号,支持多个序号
+            indexes: Vec<usize>,
+            /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
+            #[arg(short, long)]
+            username: Option<String>,
+        },
+    }
+
+    /// Rating 历史记录
+    #[derive(Subcommand)]
+    pub enum RatingSubCommands {
+        /// 查看 Rating 历史记录与变化趋势
+        History {
+            /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
+        },
+        /// 对比两次快照之间 Best 列表的变化,如：maimai-search rating diff 1 3
+        Diff {
+            /// 起始快照序号(rating history 中的序号)
+            from: usize,
+            /// 结束快照序号(rating history 中的序号)
+            to: usize,
+            /// 用户名,可选参数,如果不填写则使用配置文件中的用户名
+            #[arg(short, long)]
+            username: Option<String>,
+            /// 使用 markdown 格式输出
+            #[arg(short, long)]
+            markdown: bool,
         },
     }
 
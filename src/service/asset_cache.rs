@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use image::{DynamicImage, ImageError};
+
+/// # 绘图资源缓存
+///
+/// 缓存缩放后的图标与处理过的封面,使用 `Arc` 包装后可以在多次绘制、多个线程之间共享
+#[derive(Default)]
+pub struct AssetCache {
+    images: RwLock<HashMap<String, Arc<DynamicImage>>>,
+}
+
+impl AssetCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// # 获取缓存的图片
+    ///
+    /// 缓存中没有 `key` 对应的图片时调用 `load` 加载并放入缓存,加载失败时不会缓存
+    pub fn get_or_load<F>(&self, key: String, load: F) -> Result<Arc<DynamicImage>, ImageError>
+    where
+        F: FnOnce() -> Result<DynamicImage, ImageError>,
+    {
+        if let Some(image) = self.images.read().unwrap().get(&key) {
+            return Ok(image.clone());
+        }
+        let image = Arc::new(load()?);
+        self.images.write().unwrap().insert(key, image.clone());
+        Ok(image)
+    }
+
+    /// 缓存中的图片数量
+    pub fn len(&self) -> usize {
+        self.images.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,322 @@
+use clap::ValueEnum;
+use image::ImageOutputFormat;
+use prettytable::{row, Cell, Row, Table};
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+use crate::clients::user_data::entity::{dx_percent, dx_star, ChartRate, LevelLabel};
+use crate::config::consts::DIFFICULT_NAME;
+use crate::service::chart_index::ChartIndex;
+use crate::service::maimai_best_50::BestList;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// # B50 输出格式
+///
+/// 除了图片之外,还可以输出成表格或数据,方便导入表格软件或者发到图片会被压缩的群聊里
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum BestFormat {
+    /// PNG 图片
+    Png,
+    /// JPEG 图片,体积更小,可以通过质量参数调整
+    Jpeg,
+    /// WebP 图片(无损)
+    Webp,
+    /// JSON 数据
+    Json,
+    /// CSV 表格
+    Csv,
+    /// Markdown 表格
+    Md,
+    /// 命令行表格
+    Table,
+}
+
+impl BestFormat {
+    /// 图片格式对应的编码方式,文本格式返回 `None`
+    ///
+    /// - `quality` 为 1 ~ 100 的图片质量,目前只对 JPEG 生效
+    pub fn image_format(&self, quality: u8) -> Option<ImageOutputFormat> {
+        match self {
+            BestFormat::Png => Some(ImageOutputFormat::Png),
+            BestFormat::Jpeg => Some(ImageOutputFormat::Jpeg(quality)),
+            BestFormat::Webp => Some(ImageOutputFormat::WebP),
+            _ => None,
+        }
+    }
+
+    /// 输出文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BestFormat::Png => "png",
+            BestFormat::Jpeg => "jpg",
+            BestFormat::Webp => "webp",
+            BestFormat::Json => "json",
+            BestFormat::Csv => "csv",
+            BestFormat::Md => "md",
+            BestFormat::Table => "txt",
+        }
+    }
+}
+
+/// 导出的单张谱面
+#[derive(Serialize)]
+pub struct ExportChart {
+    /// 在列表中的排名,从 1 开始
+    pub rank: usize,
+    pub title: String,
+    #[serde(rename = "type")]
+    pub song_type: String,
+    #[serde(serialize_with = "serialize_display")]
+    pub level_label: LevelLabel,
+    pub ds: f32,
+    pub achievements: f32,
+    #[serde(serialize_with = "serialize_display")]
+    pub rate: ChartRate,
+    #[serde(serialize_with = "serialize_combo")]
+    pub fc: String,
+    #[serde(serialize_with = "serialize_combo")]
+    pub fs: String,
+    pub dx_score: i32,
+    /// 理论 DX 分数,本地没有谱面数据时为空
+    pub max_dx_score: Option<i32>,
+    /// DX 分数百分比
+    pub dx_percent: Option<f32>,
+    /// DX 分数星级(0 ~ 5)
+    pub dx_star: Option<u8>,
+    pub ra: i32,
+}
+
+/// 导出的单个列表
+#[derive(Serialize)]
+pub struct ExportList {
+    /// 列表名称,例如 B35
+    pub name: String,
+    /// 列表内谱面的 Rating 总和
+    pub rating: i32,
+    pub charts: Vec<ExportChart>,
+}
+
+/// # 导出的 B50 数据
+///
+/// 所有文本格式都从这个结构体生成,保证各个格式的内容一致,
+/// 难度、评级与 FC/FS 在 JSON 中同样使用显示名称(如 `MASTER`、`SSS+`、`AP+`)
+#[derive(Serialize)]
+pub struct BestExport {
+    pub username: String,
+    pub nickname: String,
+    /// 总 Rating
+    pub rating: i32,
+    /// 旧版本列表
+    pub sd: ExportList,
+    /// 新版本列表
+    pub dx: ExportList,
+}
+
+impl ExportList {
+    fn new(best: &BestList, index: Option<&ChartIndex>) -> Self {
+        ExportList {
+            name: format!("B{}", best.size()),
+            rating: best.rating(),
+            charts: best
+                .iter()
+                .enumerate()
+                .map(|(rank, chart)| {
+                    (
+                        rank,
+                        chart,
+                        index
+                            .and_then(|index| index.max_dx_score(chart.song_id, chart.level_label)),
+                    )
+                })
+                .map(|(rank, chart, max_dx_score)| ExportChart {
+                    rank: rank + 1,
+                    title: chart.title.clone(),
+                    song_type: chart.song_type.clone(),
+                    level_label: chart.level_label,
+                    ds: chart.ds,
+                    achievements: chart.achievements,
+                    rate: chart.rate,
+                    fc: chart.fc.clone(),
+                    fs: chart.fs.clone(),
+                    dx_score: chart.dx_score,
+                    max_dx_score,
+                    dx_percent: max_dx_score.map(|max| dx_percent(chart.dx_score, max)),
+                    dx_star: max_dx_score.map(|max| dx_star(chart.dx_score, max)),
+                    ra: chart.ra,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl BestExport {
+    /// 传入本地谱面索引时会计算 DX 分数百分比与星级
+    pub fn new(
+        sd_best: &BestList,
+        dx_best: &BestList,
+        username: &str,
+        nickname: &str,
+        index: Option<&ChartIndex>,
+    ) -> Self {
+        BestExport {
+            username: username.to_string(),
+            nickname: nickname.to_string(),
+            rating: sd_best.rating() + dx_best.rating(),
+            sd: ExportList::new(sd_best, index),
+            dx: ExportList::new(dx_best, index),
+        }
+    }
+
+    /// 输出为 JSON 字符串
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// # 输出为 CSV 字符串
+    ///
+    /// 每个列表的末尾有一行合计,只填写列表名称与 Rating 总和
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "list,rank,title,type,difficulty,ds,achievements,rate,fc,fs,dx_score,max_dx_score,dx_percent,dx_star,ra\n",
+        );
+        for list in [&self.sd, &self.dx] {
+            for chart in &list.charts {
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{},{:.4},{},{},{},{},{},{},{},{}\n",
+                    list.name,
+                    chart.rank,
+                    csv_field(&chart.title),
+                    chart.song_type,
+                    chart.level_label,
+                    chart.ds,
+                    chart.achievements,
+                    chart.rate,
+                    combo_name(&chart.fc),
+                    combo_name(&chart.fs),
+                    chart.dx_score,
+                    optional(chart.max_dx_score),
+                    optional(chart.dx_percent.map(|percent| format!("{:.2}", percent))),
+                    optional(chart.dx_star),
+                    chart.ra
+                ));
+            }
+            csv.push_str(&format!("{},,合计,,,,,,,,,,,,{}\n", list.name, list.rating));
+        }
+        csv
+    }
+
+    /// # 构建表格
+    ///
+    /// 第一张表为 Rating 总览,之后是两个列表的详情,用于命令行与 Markdown 输出
+    pub fn tables(&self) -> Vec<SongTable> {
+        let mut table = Table::new();
+        table.set_titles(row!["列表", "谱面数", "Rating"]);
+        for list in [&self.sd, &self.dx] {
+            table.add_row(row![list.name, list.charts.len(), list.rating]);
+        }
+        table.add_row(row!["合计", "", self.rating]);
+        let mut table_vec = vec![SongTable {
+            info: format!("{} 的 B50 (Rating: {})", self.nickname, self.rating),
+            table,
+            head: MarkdownFormat::H2,
+        }];
+
+        for list in [&self.sd, &self.dx] {
+            let mut table = Table::new();
+            table.set_titles(row![
+                "排名",
+                "乐曲标题",
+                "类型",
+                "难度",
+                "定数",
+                "达成率",
+                "FC/FS",
+                "DX 分数",
+                "星级",
+                "Rating"
+            ]);
+            for chart in &list.charts {
+                let mut row = Row::empty();
+                row.add_cell(Cell::new(&format!("#{}", chart.rank)));
+                row.add_cell(Cell::new(&chart.title));
+                row.add_cell(Cell::new(&chart.song_type));
+                row.add_cell(DIFFICULT_NAME[chart.level_label as usize].clone());
+                row.add_cell(Cell::new(&chart.ds.to_string()));
+                row.add_cell(Cell::new(&format!(
+                    "{:.4}% ({})",
+                    chart.achievements, chart.rate
+                )));
+                row.add_cell(Cell::new(&format!(
+                    "{} / {}",
+                    combo_name(&chart.fc),
+                    combo_name(&chart.fs)
+                )));
+                row.add_cell(Cell::new(&match (chart.max_dx_score, chart.dx_percent) {
+                    (Some(max), Some(percent)) => {
+                        format!("{} / {} ({:.2}%)", chart.dx_score, max, percent)
+                    }
+                    _ => chart.dx_score.to_string(),
+                }));
+                row.add_cell(Cell::new(&match chart.dx_star {
+                    Some(star) if star > 0 => "★".repeat(star as usize),
+                    _ => "-".to_string(),
+                }));
+                row.add_cell(Cell::new(&chart.ra.to_string()));
+                table.add_row(row);
+            }
+            table_vec.push(SongTable {
+                info: format!("{} (Rating: {})", list.name, list.rating),
+                table,
+                head: MarkdownFormat::H3,
+            });
+        }
+        table_vec
+    }
+}
+
+/// # FC/FS 的显示名称
+///
+/// 查分器返回的是小写缩写,例如 `app` 表示 AP+,`fsdp` 表示 FDX+,没有时显示为 `-`
+pub fn combo_name(combo: &str) -> String {
+    match combo {
+        "" => "-",
+        "fc" => "FC",
+        "fcp" => "FC+",
+        "ap" => "AP",
+        "app" => "AP+",
+        "sync" => "SYNC",
+        "fs" => "FS",
+        "fsp" => "FS+",
+        "fsd" => "FDX",
+        "fsdp" => "FDX+",
+        other => other,
+    }
+    .to_string()
+}
+
+/// 按照 `Display` 的写法序列化
+fn serialize_display<T: fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// FC/FS 按照显示名称序列化
+fn serialize_combo<S: Serializer>(combo: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&combo_name(combo))
+}
+
+/// CSV 中的可选字段,为空时留空
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// CSV 字段转义,包含逗号、引号或换行时用引号包裹
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
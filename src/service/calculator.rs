@@ -0,0 +1,75 @@
+use prettytable::{Cell, Row, Table};
+
+use crate::clients::user_data::entity::{compute_ra, ChartRate};
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// Rating 表中列出的评级,从低到高排列
+const TABLE_RATES: [ChartRate; 9] = [
+    ChartRate::A,
+    ChartRate::AA,
+    ChartRate::AAA,
+    ChartRate::S,
+    ChartRate::SP,
+    ChartRate::SS,
+    ChartRate::SSP,
+    ChartRate::SSS,
+    ChartRate::SSSP,
+];
+
+/// # Rating 计算器
+///
+/// 把 `compute_ra` 暴露给命令行使用,顺便取代手里的那张 Excel 表
+pub struct RatingCalculator;
+
+impl RatingCalculator {
+    /// # 定数 × 评级 Rating 表
+    ///
+    /// 每行是一个定数(步长 0.1),每列是该评级最低达成率对应的单曲 Rating
+    pub fn ra_table(from: f32, to: f32) -> SongTable {
+        let mut table = Table::new();
+        let mut title = Row::new(vec![Cell::new("定数")]);
+        for rate in TABLE_RATES {
+            title.add_cell(Cell::new(&format!("{}({})", rate, rate.min_achievement())));
+        }
+        table.set_titles(title);
+
+        let (from, to) = ((from * 10.0).round() as i32, (to * 10.0).round() as i32);
+        for ds in (from..=to).map(|ds| ds as f32 / 10.0) {
+            let mut row = Row::new(vec![Cell::new(&format!("{:.1}", ds))]);
+            for rate in TABLE_RATES {
+                row.add_cell(Cell::new(
+                    &compute_ra(ds, rate.min_achievement()).to_string(),
+                ));
+            }
+            table.add_row(row);
+        }
+        SongTable {
+            info: format!(
+                "定数 {:.1} ~ {:.1} Rating 表",
+                from as f32 / 10.0,
+                to as f32 / 10.0
+            ),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+}
+
+/// # 解析定数区间
+///
+/// 格式为 `起始..结束`,例如 `13.0..15.0`,起始定数不能大于结束定数
+pub fn parse_ds_range(range: &str) -> Result<(f32, f32), String> {
+    let (from, to) = range
+        .split_once("..")
+        .ok_or(format!("定数区间[{}]格式错误,正确格式如 13.0..15.0", range))?;
+    let parse = |ds: &str| {
+        ds.trim()
+            .parse::<f32>()
+            .map_err(|_| format!("无法解析定数[{}]", ds))
+    };
+    let (from, to) = (parse(from)?, parse(to)?);
+    if from > to {
+        return Err(format!("起始定数[{}]不能大于结束定数[{}]", from, to));
+    }
+    Ok((from, to))
+}
@@ -0,0 +1,194 @@
+use std::collections::{HashMap, HashSet};
+
+use log::warn;
+use prettytable::{row, Cell, Row, Table};
+
+use crate::clients::song_data;
+use crate::clients::song_data::entity::{Chart, Song};
+use crate::clients::user_data::entity::{ChartInfoResponse, LevelLabel, RatingRule};
+use crate::config::consts::DIFFICULT_NAME;
+use crate::db::database::MaimaiDB;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// # 本地谱面索引
+///
+/// 查分器返回的 `song_id` 与本地数据库的歌曲 ID 相同,配合难度标签就能定位到唯一的谱面
+pub struct ChartIndex {
+    songs: HashMap<usize, Song>,
+}
+
+/// 本地数据库中的单张谱面
+pub struct IndexedChart<'a> {
+    pub song: &'a Song,
+    pub level_label: LevelLabel,
+    pub ds: f32,
+}
+
+/// 查分器与本地数据库不一致的谱面
+pub struct ChartDiff {
+    /// 歌曲标题
+    pub title: String,
+    /// 歌曲类型
+    pub song_type: String,
+    /// 难度标签
+    pub level_label: LevelLabel,
+    /// 查分器的定数
+    pub server_ds: f32,
+    /// 本地数据库的定数
+    pub local_ds: f32,
+    /// 查分器的 Rating
+    pub server_ra: i32,
+    /// 本地重新计算的 Rating
+    pub local_ra: i32,
+}
+
+impl ChartIndex {
+    /// 使用传入的歌曲构建索引
+    pub fn new(songs: Vec<Song>) -> Self {
+        ChartIndex {
+            songs: songs.into_iter().map(|song| (song.id, song)).collect(),
+        }
+    }
+
+    /// 使用本地数据库中的全部歌曲构建索引
+    pub fn load() -> Self {
+        Self::new(song_data::get_all_songs())
+    }
+
+    /// # 尝试加载本地数据库
+    ///
+    /// 数据库为空时返回 `None` 而不是退出,用于本地数据可有可无的场景(例如 DX 分数星级)
+    pub fn try_load() -> Option<Self> {
+        let songs = MaimaiDB::search_all_songs();
+        (!songs.is_empty()).then(|| Self::new(songs))
+    }
+
+    /// 按照 ID 获取歌曲
+    pub fn song(&self, song_id: i32) -> Option<&Song> {
+        self.songs.get(&(song_id as usize))
+    }
+
+    /// 索引内的全部歌曲
+    pub fn songs(&self) -> impl Iterator<Item = &Song> {
+        self.songs.values()
+    }
+
+    /// # 未游玩的谱面
+    ///
+    /// 按照歌曲 ID 与难度与玩家成绩对比,列出本地数据库中没有成绩的谱面(不包含宴会场谱面)
+    pub fn unplayed(&self, played: &[ChartInfoResponse]) -> Vec<IndexedChart<'_>> {
+        let played: HashSet<(i32, LevelLabel)> = played
+            .iter()
+            .map(|chart| (chart.song_id, chart.level_label))
+            .collect();
+        self.songs()
+            .filter(|song| !song.is_utage())
+            .flat_map(|song| {
+                song.ds.iter().enumerate().filter_map(|(index, ds)| {
+                    let level_label = LevelLabel::from_index(index)?;
+                    (!played.contains(&(song.id as i32, level_label))).then_some(IndexedChart {
+                        song,
+                        level_label,
+                        ds: *ds,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// 获取谱面定数
+    pub fn ds(&self, song_id: i32, level_label: LevelLabel) -> Option<f32> {
+        self.song(song_id)?.ds.get(level_label as usize).copied()
+    }
+
+    /// 获取谱面详情
+    pub fn chart(&self, song_id: i32, level_label: LevelLabel) -> Option<&Chart> {
+        self.song(song_id)?.charts.get(level_label as usize)
+    }
+
+    /// # 判断谱面是否属于当前版本
+    ///
+    /// - 指定版本时,按照歌曲的收录版本(`BasicInfo::from`)判断,可以用来计算过去或未来版本的 B50
+    /// - 不指定版本时,使用本地数据库中的 `BasicInfo::is_new`
+    ///
+    /// 本地找不到的歌曲返回 `None`
+    pub fn is_new(&self, song_id: i32, version: Option<&str>) -> Option<bool> {
+        let basic_info = &self.song(song_id)?.basic_info;
+        Some(match version {
+            Some(version) => basic_info.from == version,
+            None => basic_info.is_new,
+        })
+    }
+
+    /// 获取谱面的理论 DX 分数
+    pub fn max_dx_score(&self, song_id: i32, level_label: LevelLabel) -> Option<i32> {
+        self.chart(song_id, level_label).map(Chart::max_dx_score)
+    }
+
+    /// # 使用本地数据重新计算
+    ///
+    /// 按照 `song_id` 与难度找到本地谱面,用本地的定数与当前的 Rating 规则重新计算 `ds` 与 `ra`
+    ///
+    /// - 本地找不到的谱面保留查分器的数据,并给出警告
+    /// - 返回定数或 Rating 与查分器不一致的谱面
+    pub fn recompute(&self, charts: &mut [ChartInfoResponse], rule: RatingRule) -> Vec<ChartDiff> {
+        let mut diffs = Vec::new();
+        for chart in charts.iter_mut() {
+            let local_ds = match self.ds(chart.song_id, chart.level_label) {
+                Some(ds) => ds,
+                None => {
+                    warn!(
+                        "本地数据库中找不到谱面[{}]{} {},使用查分器数据",
+                        chart.song_id, chart.title, chart.level_label
+                    );
+                    continue;
+                }
+            };
+            let (server_ds, server_ra) = (chart.ds, chart.ra);
+            chart.ds = local_ds;
+            chart.ra = rule.chart_ra(chart);
+            if server_ds != chart.ds || server_ra != chart.ra {
+                diffs.push(ChartDiff {
+                    title: chart.title.clone(),
+                    song_type: chart.song_type.clone(),
+                    level_label: chart.level_label,
+                    server_ds,
+                    local_ds,
+                    server_ra,
+                    local_ra: chart.ra,
+                });
+            }
+        }
+        diffs
+    }
+
+    /// 构建查分器与本地数据的差异表格
+    pub fn diff_table(diffs: &[ChartDiff]) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "乐曲标题",
+            "类型",
+            "难度",
+            "定数(查分器)",
+            "定数(本地)",
+            "Rating(查分器)",
+            "Rating(本地)"
+        ]);
+        for diff in diffs {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&diff.title));
+            row.add_cell(Cell::new(&diff.song_type));
+            row.add_cell(DIFFICULT_NAME[diff.level_label as usize].clone());
+            row.add_cell(Cell::new(&diff.server_ds.to_string()));
+            row.add_cell(Cell::new(&diff.local_ds.to_string()));
+            row.add_cell(Cell::new(&diff.server_ra.to_string()));
+            row.add_cell(Cell::new(&diff.local_ra.to_string()));
+            table.add_row(row);
+        }
+        SongTable {
+            info: "查分器与本地数据不一致的谱面".to_string(),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+}
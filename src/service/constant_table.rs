@@ -0,0 +1,330 @@
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use image::imageops::{overlay, FilterType};
+use image::{DynamicImage, ImageError, ImageOutputFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use rusttype::Scale;
+
+use crate::clients::song_data::entity::Song;
+use crate::clients::user_data::entity::LevelLabel;
+use crate::config::consts::{CONFIG_PATH, LAUNCH_PATH};
+use crate::config::theme::Theme;
+use crate::service::level_sheet::LevelFilter;
+use crate::service::maimai_best_50::open_cover;
+use crate::service::table::TableService;
+use crate::utils::file::get_font;
+use crate::utils::image::encode_image;
+
+/// 封面边长
+const TILE_SIZE: u32 = 64;
+/// 封面之间的间距
+const TILE_GAP: i32 = 6;
+/// 每行的谱面数量
+const COLUMNS: usize = 14;
+/// 左侧定数标签的宽度
+const LABEL_WIDTH: i32 = 80;
+/// 边距
+const MARGIN: i32 = 24;
+/// 头部标题的高度
+const HEADER_HEIGHT: i32 = 80;
+/// 不同定数之间的额外间距
+const SECTION_GAP: i32 = 10;
+/// 谱面类型角标的尺寸
+const BADGE_SIZE: (u32, u32) = (22, 14);
+
+/// # 定数表输出格式
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum ConstantFormat {
+    /// PNG 图片
+    Png,
+    /// Markdown 文档
+    Md,
+    /// HTML 页面
+    Html,
+}
+
+impl ConstantFormat {
+    /// 输出文件的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ConstantFormat::Png => "png",
+            ConstantFormat::Md => "md",
+            ConstantFormat::Html => "html",
+        }
+    }
+}
+
+/// 定数表中的单张谱面
+pub struct ConstantChart {
+    pub song: Song,
+    pub level_label: LevelLabel,
+    pub ds: f32,
+}
+
+/// # 定数表
+///
+/// 把某个等级或定数区间内的全部谱面按照定数分行排列,每张封面带有难度颜色外框与 DX/SD 角标,
+/// 可以输出为图片,也可以输出为 Markdown 与 HTML
+pub struct ConstantTable {
+    charts: Vec<ConstantChart>,
+    filter: LevelFilter,
+    theme: Theme,
+    /// 封面目录
+    cover_dir: PathBuf,
+}
+
+impl ConstantTable {
+    /// # 初始化定数表
+    ///
+    /// 从歌曲列表中找出符合条件的谱面(不包含宴会场谱面),按照定数从高到低排列
+    pub fn new<'a>(songs: impl Iterator<Item = &'a Song>, filter: LevelFilter) -> Self {
+        let mut charts: Vec<ConstantChart> = songs
+            .filter(|song| !song.is_utage())
+            .flat_map(|song| {
+                song.level
+                    .iter()
+                    .zip(&song.ds)
+                    .enumerate()
+                    .filter(|(_, (level, ds))| filter.matches(level, **ds))
+                    .filter_map(|(index, (_, ds))| {
+                        Some(ConstantChart {
+                            song: song.clone(),
+                            level_label: LevelLabel::from_index(index)?,
+                            ds: *ds,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        charts.sort_by(|a, b| {
+            b.ds.total_cmp(&a.ds)
+                .then(a.song.id.cmp(&b.song.id))
+                .then(a.level_label.cmp(&b.level_label))
+        });
+        ConstantTable {
+            charts,
+            filter,
+            theme: Theme::default(),
+            cover_dir: CONFIG_PATH.join("resource/mai/cover"),
+        }
+    }
+
+    /// 设置主题,使用其中的背景、字体与难度颜色
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// 定数表内的谱面,按照定数从高到低排列
+    pub fn charts(&self) -> &[ConstantChart] {
+        &self.charts
+    }
+
+    /// 默认的保存位置,在程序所在目录下(`{等级}-constants.{扩展名}`)
+    pub fn output_path(&self, format: ConstantFormat) -> PathBuf {
+        let name = match &self.filter {
+            LevelFilter::Level(level) => level.clone(),
+            LevelFilter::Ds(from, to) => format!("{:.1}-{:.1}", from, to),
+        };
+        LAUNCH_PATH.join(format!("{}-constants.{}", name, format.extension()))
+    }
+
+    /// 按照定数分组,组内保持原有顺序
+    fn rows(&self) -> Vec<(f32, &[ConstantChart])> {
+        self.charts
+            .chunk_by(|a, b| (a.ds * 10.0).round() == (b.ds * 10.0).round())
+            .map(|charts| (charts[0].ds, charts))
+            .collect()
+    }
+
+    /// 标题中的描述
+    fn title(&self) -> String {
+        match &self.filter {
+            LevelFilter::Level(_) => format!("{} 定数表", self.filter.describe()),
+            LevelFilter::Ds(from, to) => format!("{:.1} ~ {:.1} 定数表", from, to),
+        }
+    }
+
+    /// # 输出为 Markdown
+    ///
+    /// 每个定数一行,封面链接由 [`TableService::get_song_picture`] 生成,
+    /// 开启本地化图片时 `output` 用于决定图片的存放目录
+    pub fn to_markdown(&self, output: &Option<String>) -> String {
+        let mut markdown = format!("## {}\n\n| 定数 | 谱面 |\n| :-: | :-- |\n", self.title());
+        for (ds, charts) in self.rows() {
+            let covers = charts
+                .iter()
+                .map(|chart| TableService::get_song_picture(&chart.song, output))
+                .collect::<Vec<_>>()
+                .join(" ");
+            markdown.push_str(&format!("| {:.1} | {} |\n", ds, covers));
+        }
+        markdown
+    }
+
+    /// # 输出为 HTML
+    ///
+    /// 与图片相同,封面带有难度颜色外框与 DX/SD 角标,封面地址由 [`TableService::get_song_picture_url`] 生成
+    pub fn to_html(&self, output: &Option<String>) -> String {
+        let mut rows = String::new();
+        for (ds, charts) in self.rows() {
+            let covers = charts
+                .iter()
+                .map(|chart| {
+                    let Rgba([r, g, b, _]) = self.theme.level_color(chart.level_label);
+                    format!(
+                        "<span class=\"chart\"><img src=\"{}\" title=\"{} [{}]\" style=\"border-color: #{:02x}{:02x}{:02x}\"><b class=\"{}\">{}</b></span>",
+                        TableService::get_song_picture_url(&chart.song, output),
+                        escape_html(&chart.song.title),
+                        chart.level_label,
+                        r,
+                        g,
+                        b,
+                        chart.song.song_type.to_lowercase(),
+                        chart.song.song_type
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("");
+            rows.push_str(&format!("<tr><th>{:.1}</th><td>{}</td></tr>\n", ds, covers));
+        }
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="zh-CN">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+th {{ font-size: 24px; padding: 0 16px; vertical-align: top; }}
+.chart {{ position: relative; display: inline-block; margin: 3px; }}
+.chart img {{ width: {size}px; height: {size}px; border: 3px solid; box-sizing: border-box; }}
+.chart b {{ position: absolute; right: 3px; bottom: 3px; font-size: 10px; color: #fff; padding: 0 3px; }}
+.dx {{ background: #ff8c00; }}
+.sd {{ background: #3c82e6; }}
+</style>
+</head>
+<body>
+<h2>{title}</h2>
+<table>
+{rows}</table>
+</body>
+</html>
+"#,
+            title = escape_html(&self.title()),
+            size = TILE_SIZE,
+            rows = rows
+        )
+    }
+
+    /// 绘制并编码为指定格式
+    pub fn encode(&self, format: ImageOutputFormat) -> Result<Vec<u8>, ImageError> {
+        encode_image(self.render()?, format)
+    }
+
+    /// # 在内存中绘制图片
+    ///
+    /// 图片高度随谱面数量变化,背景使用主题中的背景图片
+    pub fn render(&self) -> Result<RgbaImage, ImageError> {
+        let rows = self.rows();
+        let tile_span = TILE_SIZE as i32 + TILE_GAP;
+        let width = (MARGIN * 2 + LABEL_WIDTH + tile_span * COLUMNS as i32 - TILE_GAP) as u32;
+        let grid_height: i32 = rows
+            .iter()
+            .map(|(_, charts)| charts.len().div_ceil(COLUMNS) as i32 * tile_span + SECTION_GAP)
+            .sum();
+        let height = (HEADER_HEIGHT + grid_height + MARGIN) as u32;
+        let background = image::open(
+            CONFIG_PATH
+                .join("resource/mai/pic")
+                .join(&self.theme.background),
+        )?;
+        let mut img = background.resize_to_fill(width, height, FilterType::Lanczos3);
+
+        let font = get_font(&self.theme.fonts.text);
+        let name_color = Rgba(self.theme.colors.name);
+        draw_text_mut(
+            &mut img,
+            name_color,
+            MARGIN,
+            MARGIN,
+            Scale::uniform(32.0),
+            &font,
+            &self.title(),
+        );
+        let mut top = HEADER_HEIGHT;
+        for (ds, charts) in rows {
+            draw_text_mut(
+                &mut img,
+                name_color,
+                MARGIN,
+                top + 18,
+                Scale::uniform(26.0),
+                &font,
+                &format!("{:.1}", ds),
+            );
+            for (num, chart) in charts.iter().enumerate() {
+                let x = MARGIN + LABEL_WIDTH + tile_span * (num % COLUMNS) as i32;
+                let y = top + tile_span * (num / COLUMNS) as i32;
+                overlay(&mut img, &self.draw_tile(chart)?, x as i64, y as i64);
+            }
+            top += charts.len().div_ceil(COLUMNS) as i32 * tile_span + SECTION_GAP;
+        }
+        Ok(img.to_rgba8())
+    }
+
+    /// 绘制单张谱面:难度颜色外框与右下角的 DX/SD 角标
+    fn draw_tile(&self, chart: &ConstantChart) -> Result<DynamicImage, ImageError> {
+        let mut tile = open_cover(&self.cover_dir, chart.song.id as i32)?.resize_exact(
+            TILE_SIZE,
+            TILE_SIZE,
+            FilterType::Lanczos3,
+        );
+        let level_color = self.theme.level_color(chart.level_label);
+        for inset in 0..3 {
+            let size = TILE_SIZE - 2 * inset as u32;
+            draw_hollow_rect_mut(
+                &mut tile,
+                Rect::at(inset, inset).of_size(size, size),
+                level_color,
+            );
+        }
+
+        let (badge_width, badge_height) = BADGE_SIZE;
+        let badge_color = match chart.song.song_type.as_str() {
+            "DX" => Rgba([255, 140, 0, 255]),
+            _ => Rgba([60, 130, 230, 255]),
+        };
+        let mut badge = RgbaImage::new(badge_width, badge_height);
+        draw_filled_rect_mut(
+            &mut badge,
+            Rect::at(0, 0).of_size(badge_width, badge_height),
+            badge_color,
+        );
+        draw_text_mut(
+            &mut badge,
+            Rgba([255, 255, 255, 255]),
+            3,
+            1,
+            Scale::uniform(12.0),
+            &get_font(&self.theme.fonts.text),
+            &chart.song.song_type,
+        );
+        overlay(
+            &mut tile,
+            &badge,
+            (TILE_SIZE - badge_width - 3) as i64,
+            (TILE_SIZE - badge_height - 3) as i64,
+        );
+        Ok(tile)
+    }
+}
+
+/// 转义 HTML 中的特殊字符
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::exit;
+use std::{fs, io};
+
+use chrono::{DateTime, Local};
+use log::{error, info, warn};
+use prettytable::{row, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::clients::user_data::entity::{ChartInfoResponse, LevelLabel};
+use crate::config::consts::CONFIG_PATH;
+use crate::service::maimai_best_50::BestList;
+use crate::service::printer::PrinterHandler;
+use crate::service::table::{MarkdownFormat, SongTable};
+use crate::utils::file::create_dir;
+
+/// 趋势图使用的字符,从低到高排列
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// # Rating 快照
+///
+/// 每次生成 B50 时记录一次,保存在配置文件夹的 `history` 目录下,每个用户对应一个 json 文件
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RatingSnapshot {
+    /// 快照时间
+    pub timestamp: DateTime<Local>,
+    /// 查分器用户名
+    pub username: String,
+    /// 用户 Rating(B35 + B15)
+    pub rating: i32,
+    /// B35 Rating
+    pub sd_rating: i32,
+    /// B15 Rating
+    pub dx_rating: i32,
+    /// B35 底分
+    pub sd_floor: i32,
+    /// B15 底分
+    pub dx_floor: i32,
+    /// B35 谱面列表
+    pub sd_charts: Vec<ChartInfoResponse>,
+    /// B15 谱面列表
+    pub dx_charts: Vec<ChartInfoResponse>,
+}
+
+impl RatingSnapshot {
+    /// 使用当前的 Best 列表创建快照
+    pub fn new(username: &str, sd_best: &BestList, dx_best: &BestList) -> Self {
+        let sd_rating = sd_best.rating();
+        let dx_rating = dx_best.rating();
+        RatingSnapshot {
+            timestamp: Local::now(),
+            username: username.to_string(),
+            rating: sd_rating + dx_rating,
+            sd_rating,
+            dx_rating,
+            sd_floor: sd_best.floor(),
+            dx_floor: dx_best.floor(),
+            sd_charts: sd_best.iter().cloned().collect(),
+            dx_charts: dx_best.iter().cloned().collect(),
+        }
+    }
+}
+
+/// # Rating 历史记录
+///
+/// 负责快照的读写,以及历史趋势和快照对比的输出
+pub struct HistoryService;
+
+impl HistoryService {
+    /// # 保存快照
+    ///
+    /// 快照会追加到用户历史记录的尾部,保存失败只会产生警告,不会打断调用方的流程;
+    /// 已有的历史记录无法读取时不会覆盖原文件,本次快照直接放弃
+    pub fn save(snapshot: RatingSnapshot) {
+        let path = Self::history_path(&snapshot.username);
+        let mut snapshots = match Self::try_load(&snapshot.username) {
+            Ok(snapshots) => snapshots,
+            Err(error) => {
+                warn!(
+                    "读取 Rating 历史记录[{}]失败,本次快照不会保存\n[Cause]:{:?}",
+                    path.display(),
+                    error
+                );
+                return;
+            }
+        };
+        snapshots.push(snapshot);
+        create_dir(&CONFIG_PATH.join("history"));
+        let json = match serde_json::to_string(&snapshots) {
+            Ok(json) => json,
+            Err(error) => {
+                warn!("序列化 Rating 快照失败\n[Cause]:{:?}", error);
+                return;
+            }
+        };
+        match fs::write(&path, json) {
+            Ok(_) => info!("Rating 快照已保存:[{}]", path.display()),
+            Err(error) => warn!("保存 Rating 快照失败\n[Cause]:{:?}", error),
+        }
+    }
+
+    /// # 读取用户的全部快照
+    ///
+    /// 按照记录的先后顺序排列,没有历史记录时返回空列表,文件无法解析时错误类型为 `InvalidData`
+    pub fn try_load(username: &str) -> io::Result<Vec<RatingSnapshot>> {
+        let path = Self::history_path(username);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let json = fs::read_to_string(&path)?;
+        serde_json::from_str(&json)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    /// 读取用户的全部快照,失败时直接退出,供 `rating history` 与 `rating diff` 使用
+    pub fn load(username: &str) -> Vec<RatingSnapshot> {
+        Self::try_load(username).unwrap_or_else(|error| {
+            error!(
+                "读取 Rating 历史记录[{}]失败\n[Cause]:{:?}",
+                Self::history_path(username).display(),
+                error
+            );
+            match error.kind() {
+                io::ErrorKind::InvalidData => exit(exitcode::DATAERR),
+                _ => exit(exitcode::IOERR),
+            }
+        })
+    }
+
+    /// 输出 Rating 历史表格与趋势图
+    pub fn print_history(username: &str, markdown: bool) {
+        let snapshots = Self::load(username);
+        if snapshots.is_empty() {
+            warn!(
+                "用户[{}]还没有 Rating 快照,运行一次 b50 后会自动记录",
+                username
+            );
+            exit(exitcode::OK)
+        }
+        let ratings: Vec<i32> = snapshots.iter().map(|snapshot| snapshot.rating).collect();
+        PrinterHandler::table_handler(vec![Self::history_table(&snapshots)], markdown);
+        println!("\n{}", sparkline(&ratings));
+    }
+
+    /// # 输出两次快照之间的差异
+    ///
+    /// `from` 与 `to` 为历史表格中的序号(从 1 开始)
+    pub fn print_diff(username: &str, from: usize, to: usize, markdown: bool) {
+        let snapshots = Self::load(username);
+        let get_snapshot = |index: usize| match index.checked_sub(1) {
+            Some(index) if index < snapshots.len() => &snapshots[index],
+            _ => {
+                error!(
+                    "快照序号[{}]不存在,可用的序号为 1 ~ {}",
+                    index,
+                    snapshots.len()
+                );
+                exit(exitcode::USAGE)
+            }
+        };
+        let (from, to) = (get_snapshot(from), get_snapshot(to));
+        println!(
+            "Rating: {} -> {} ({:+})",
+            from.rating,
+            to.rating,
+            to.rating - from.rating
+        );
+        let table_vec = vec![
+            Self::diff_table("B35 变化", &from.sd_charts, &to.sd_charts),
+            Self::diff_table("B15 变化", &from.dx_charts, &to.dx_charts),
+        ];
+        PrinterHandler::table_handler(table_vec, markdown);
+    }
+
+    /// 构建 Rating 历史表格
+    fn history_table(snapshots: &[RatingSnapshot]) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "序号",
+            "时间",
+            "Rating",
+            "变化",
+            "B35",
+            "B15",
+            "B35 底分",
+            "B15 底分"
+        ]);
+        let mut last_rating = None;
+        for (index, snapshot) in snapshots.iter().enumerate() {
+            let change = match last_rating {
+                Some(last) => format!("{:+}", snapshot.rating - last),
+                None => "-".to_string(),
+            };
+            table.add_row(row![
+                index + 1,
+                snapshot.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                snapshot.rating,
+                change,
+                snapshot.sd_rating,
+                snapshot.dx_rating,
+                snapshot.sd_floor,
+                snapshot.dx_floor
+            ]);
+            last_rating = Some(snapshot.rating);
+        }
+        SongTable {
+            info: format!("{} 的 Rating 历史", snapshots[0].username),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+
+    /// # 构建单个 Best 列表的差异表格
+    ///
+    /// 谱面使用歌曲 ID 与难度作为唯一标识,分为进入、离开、变化三种状态
+    fn diff_table(info: &str, from: &[ChartInfoResponse], to: &[ChartInfoResponse]) -> SongTable {
+        let key = |chart: &ChartInfoResponse| (chart.song_id, chart.level_label);
+        let from_map: HashMap<(i32, LevelLabel), &ChartInfoResponse> =
+            from.iter().map(|chart| (key(chart), chart)).collect();
+        let to_map: HashMap<(i32, LevelLabel), &ChartInfoResponse> =
+            to.iter().map(|chart| (key(chart), chart)).collect();
+
+        let mut table = Table::new();
+        table.set_titles(row![
+            "状态",
+            "乐曲标题",
+            "类型",
+            "难度",
+            "定数",
+            "达成率",
+            "Rating",
+            "变化"
+        ]);
+        for chart in to {
+            match from_map.get(&key(chart)) {
+                None => table.add_row(row![
+                    "进入",
+                    chart.title,
+                    chart.song_type,
+                    chart.level_label,
+                    chart.ds,
+                    format!("{:.4}%", chart.achievements),
+                    chart.ra,
+                    format!("{:+}", chart.ra)
+                ]),
+                Some(old) if old.ra != chart.ra => table.add_row(row![
+                    "变化",
+                    chart.title,
+                    chart.song_type,
+                    chart.level_label,
+                    chart.ds,
+                    format!("{:.4}% -> {:.4}%", old.achievements, chart.achievements),
+                    format!("{} -> {}", old.ra, chart.ra),
+                    format!("{:+}", chart.ra - old.ra)
+                ]),
+                Some(_) => continue,
+            };
+        }
+        for chart in from
+            .iter()
+            .filter(|chart| !to_map.contains_key(&key(chart)))
+        {
+            table.add_row(row![
+                "离开",
+                chart.title,
+                chart.song_type,
+                chart.level_label,
+                chart.ds,
+                format!("{:.4}%", chart.achievements),
+                chart.ra,
+                format!("{:+}", -chart.ra)
+            ]);
+        }
+        SongTable {
+            info: info.to_string(),
+            table,
+            head: MarkdownFormat::H3,
+        }
+    }
+
+    fn history_path(username: &str) -> PathBuf {
+        CONFIG_PATH
+            .join("history")
+            .join(format!("{}.json", username))
+    }
+}
+
+/// # 趋势图
+///
+/// 将一组数值按照最小值到最大值的区间映射到 8 级方块字符上,数值全部相等时取中间高度
+pub fn sparkline(values: &[i32]) -> String {
+    let min = values.iter().min().copied().unwrap_or(0);
+    let max = values.iter().max().copied().unwrap_or(0);
+    values
+        .iter()
+        .map(|value| match max - min {
+            0 => SPARK_CHARS[SPARK_CHARS.len() / 2],
+            range => {
+                let level = (value - min) * (SPARK_CHARS.len() as i32 - 1) / range;
+                SPARK_CHARS[level as usize]
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,388 @@
+use prettytable::{row, Cell, Row, Table};
+
+use crate::clients::song_data::entity::Chart;
+use crate::clients::user_data::entity::ChartRate;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// 容错表中列出的评级
+const TOLERANCE_RATES: [ChartRate; 2] = [ChartRate::SSS, ChartRate::SSSP];
+
+/// 浮点误差,判断是否达到阈值时使用
+const EPSILON: f64 = 1e-9;
+
+/// # 谱面的 Note 数量
+///
+/// 对应查分器 `Chart::notes`,标准谱面为 `[TAP, HOLD, SLIDE, BREAK]`,DX 谱面为 `[TAP, HOLD, SLIDE, TOUCH, BREAK]`
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct NoteCounts {
+    pub tap: u32,
+    pub hold: u32,
+    pub slide: u32,
+    pub touch: u32,
+    pub breaks: u32,
+}
+
+impl NoteCounts {
+    /// 从查分器的 Note 数量分布解析,长度不是 4 或 5 时返回 `None`
+    pub fn from_notes(notes: &[u32]) -> Option<Self> {
+        match *notes {
+            [tap, hold, slide, breaks] => Some(NoteCounts {
+                tap,
+                hold,
+                slide,
+                touch: 0,
+                breaks,
+            }),
+            [tap, hold, slide, touch, breaks] => Some(NoteCounts {
+                tap,
+                hold,
+                slide,
+                touch,
+                breaks,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn from_chart(chart: &Chart) -> Option<Self> {
+        Self::from_notes(&chart.notes)
+    }
+
+    /// Note 总数
+    pub fn total(&self) -> u32 {
+        self.tap + self.hold + self.slide + self.touch + self.breaks
+    }
+
+    /// # 基础分总权重
+    ///
+    /// TAP 与 TOUCH 为 1,HOLD 为 2,SLIDE 为 3,BREAK 为 5
+    fn base_weight(&self) -> u32 {
+        self.tap + self.hold * 2 + self.slide * 3 + self.touch + self.breaks * 5
+    }
+}
+
+/// # 普通 Note 的判定
+///
+/// 只记录非 CRITICAL PERFECT 的数量,其余的都视为 CRITICAL PERFECT
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct JudgeCounts {
+    pub perfect: u32,
+    pub great: u32,
+    pub good: u32,
+    pub miss: u32,
+}
+
+impl JudgeCounts {
+    fn count(&self) -> u32 {
+        self.perfect + self.great + self.good + self.miss
+    }
+
+    /// 损失的基础分比例(以单个 Note 的基础分为 1),PERFECT 不扣基础分
+    fn base_loss(&self) -> f64 {
+        self.great as f64 * 0.2 + self.good as f64 * 0.5 + self.miss as f64
+    }
+
+    /// 损失的 DX 分数,CRITICAL PERFECT 为 3 分,PERFECT 2 分,GREAT 1 分
+    fn dx_loss(&self) -> u32 {
+        self.perfect + self.great * 2 + (self.good + self.miss) * 3
+    }
+}
+
+/// # BREAK 的判定
+///
+/// 同样只记录非 CRITICAL PERFECT 的数量,PERFECT 与 GREAT 按照分数细分
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct BreakJudgeCounts {
+    /// PERFECT 2550(50 落)
+    pub perfect_high: u32,
+    /// PERFECT 2500(100 落)
+    pub perfect_low: u32,
+    /// GREAT 2000
+    pub great_high: u32,
+    /// GREAT 1500
+    pub great_mid: u32,
+    /// GREAT 1250
+    pub great_low: u32,
+    pub good: u32,
+    pub miss: u32,
+}
+
+impl BreakJudgeCounts {
+    fn count(&self) -> u32 {
+        self.perfect_high
+            + self.perfect_low
+            + self.great_high
+            + self.great_mid
+            + self.great_low
+            + self.good
+            + self.miss
+    }
+
+    /// 损失的基础分比例,GREAT 分别保留 80%、60%、50%,GOOD 保留 40%
+    fn base_loss(&self) -> f64 {
+        self.great_high as f64 * 0.2
+            + self.great_mid as f64 * 0.4
+            + self.great_low as f64 * 0.5
+            + self.good as f64 * 0.6
+            + self.miss as f64
+    }
+
+    /// 损失的绝赞分比例,PERFECT 分别保留 75%、50%,GREAT 保留 40%,GOOD 保留 30%
+    fn bonus_loss(&self) -> f64 {
+        self.perfect_high as f64 * 0.25
+            + self.perfect_low as f64 * 0.5
+            + (self.great_high + self.great_mid + self.great_low) as f64 * 0.6
+            + self.good as f64 * 0.7
+            + self.miss as f64
+    }
+
+    fn dx_loss(&self) -> u32 {
+        self.perfect_high
+            + self.perfect_low
+            + (self.great_high + self.great_mid + self.great_low) * 2
+            + (self.good + self.miss) * 3
+    }
+}
+
+/// # 一次游玩的全部判定
+///
+/// 默认值为全部 CRITICAL PERFECT
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Judgement {
+    pub tap: JudgeCounts,
+    pub hold: JudgeCounts,
+    pub slide: JudgeCounts,
+    pub touch: JudgeCounts,
+    pub breaks: BreakJudgeCounts,
+}
+
+/// 根据判定计算出的成绩
+pub struct JudgeResult {
+    /// 达成率,与游戏内一致截断到 4 位小数
+    pub achievements: f32,
+    pub dx_score: i32,
+    pub max_dx_score: i32,
+}
+
+/// # 单个评级的容错
+///
+/// 每一项都是在其余 Note 全部 CRITICAL PERFECT 时,单独出现该判定最多允许的数量
+pub struct Tolerance {
+    pub rate: ChartRate,
+    /// 允许损失的达成率(百分点)
+    pub allowance: f64,
+    pub tap_great: u32,
+    pub hold_great: u32,
+    pub slide_great: u32,
+    /// 谱面没有 TOUCH 时为空
+    pub touch_great: Option<u32>,
+    /// BREAK 50 落
+    pub break_perfect_high: u32,
+    /// BREAK 100 落
+    pub break_perfect_low: u32,
+    /// BREAK GREAT 2000
+    pub break_great: u32,
+}
+
+/// # 判定计算器
+///
+/// 按照 maimai でらっくす 的规则由判定计算达成率与 DX 分数:
+///
+/// - 基础分 100%,按照 Note 权重(TAP/TOUCH 1,HOLD 2,SLIDE 3,BREAK 5)分配
+/// - 绝赞分 1%,平均分配给全部 BREAK
+/// - DX 分数每个 Note 最多 3 分
+pub struct JudgementCalculator {
+    notes: NoteCounts,
+}
+
+impl JudgementCalculator {
+    pub fn new(notes: NoteCounts) -> Self {
+        JudgementCalculator { notes }
+    }
+
+    pub fn notes(&self) -> NoteCounts {
+        self.notes
+    }
+
+    /// 单个基础分权重对应的达成率
+    fn base_unit(&self) -> f64 {
+        100.0 / self.notes.base_weight().max(1) as f64
+    }
+
+    /// 单个 BREAK 的绝赞分对应的达成率
+    fn bonus_unit(&self) -> f64 {
+        1.0 / self.notes.breaks.max(1) as f64
+    }
+
+    /// # 计算成绩
+    ///
+    /// 某种 Note 的判定数量超过谱面中的数量时返回错误
+    pub fn score(&self, judgement: &Judgement) -> Result<JudgeResult, String> {
+        let notes = &self.notes;
+        for (name, count, total) in [
+            ("TAP", judgement.tap.count(), notes.tap),
+            ("HOLD", judgement.hold.count(), notes.hold),
+            ("SLIDE", judgement.slide.count(), notes.slide),
+            ("TOUCH", judgement.touch.count(), notes.touch),
+            ("BREAK", judgement.breaks.count(), notes.breaks),
+        ] {
+            if count > total {
+                return Err(format!(
+                    "{} 的判定数量 {} 超过了谱面中的数量 {}",
+                    name, count, total
+                ));
+            }
+        }
+        let base_loss = judgement.tap.base_loss()
+            + judgement.hold.base_loss() * 2.0
+            + judgement.slide.base_loss() * 3.0
+            + judgement.touch.base_loss()
+            + judgement.breaks.base_loss() * 5.0;
+        let bonus_loss = judgement.breaks.bonus_loss();
+        let achievements = 101.0 - base_loss * self.base_unit() - bonus_loss * self.bonus_unit();
+        let dx_loss = judgement.tap.dx_loss()
+            + judgement.hold.dx_loss()
+            + judgement.slide.dx_loss()
+            + judgement.touch.dx_loss()
+            + judgement.breaks.dx_loss();
+        let max_dx_score = notes.total() as i32 * 3;
+        Ok(JudgeResult {
+            achievements: ((achievements * 10000.0 + EPSILON).floor() / 10000.0) as f32,
+            dx_score: max_dx_score - dx_loss as i32,
+            max_dx_score,
+        })
+    }
+
+    /// # 计算容错
+    ///
+    /// 按照 SSS、SSS+ 的最低达成率计算各种判定最多允许的数量
+    pub fn tolerance(&self) -> Vec<Tolerance> {
+        let base = self.base_unit();
+        let bonus = self.bonus_unit();
+        TOLERANCE_RATES
+            .iter()
+            .map(|rate| {
+                let allowance = 101.0 - rate.min_achievement() as f64;
+                let count = |loss: f64| ((allowance + EPSILON) / loss).floor() as u32;
+                Tolerance {
+                    rate: *rate,
+                    allowance,
+                    tap_great: count(base * 0.2).min(self.notes.tap),
+                    hold_great: count(base * 0.4).min(self.notes.hold),
+                    slide_great: count(base * 0.6).min(self.notes.slide),
+                    touch_great: (self.notes.touch > 0)
+                        .then(|| count(base * 0.2).min(self.notes.touch)),
+                    break_perfect_high: count(bonus * 0.25).min(self.notes.breaks),
+                    break_perfect_low: count(bonus * 0.5).min(self.notes.breaks),
+                    break_great: count(base + bonus * 0.6).min(self.notes.breaks),
+                }
+            })
+            .collect()
+    }
+
+    /// 容错表格
+    pub fn tolerance_table(&self, info: &str) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "评级",
+            "容错",
+            "TAP GREAT",
+            "HOLD GREAT",
+            "SLIDE GREAT",
+            "TOUCH GREAT",
+            "BREAK 50 落",
+            "BREAK 100 落",
+            "BREAK GREAT"
+        ]);
+        for tolerance in self.tolerance() {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&tolerance.rate.to_string()));
+            row.add_cell(Cell::new(&format!("{:.4}%", tolerance.allowance)));
+            for count in [
+                Some(tolerance.tap_great),
+                Some(tolerance.hold_great),
+                Some(tolerance.slide_great),
+                tolerance.touch_great,
+                Some(tolerance.break_perfect_high),
+                Some(tolerance.break_perfect_low),
+                Some(tolerance.break_great),
+            ] {
+                row.add_cell(Cell::new(&match count {
+                    Some(count) => count.to_string(),
+                    None => "-".to_string(),
+                }));
+            }
+            table.add_row(row);
+        }
+        SongTable {
+            info: format!("{} 容错表", info),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+}
+
+/// # 解析普通 Note 的判定
+///
+/// 格式为 `PERFECT,GREAT,GOOD,MISS`,可以省略末尾的项,例如 `0,3` 表示 3 个 GREAT
+pub fn parse_judge_counts(value: &str) -> Result<JudgeCounts, String> {
+    let counts = parse_counts(value, 4)?;
+    Ok(JudgeCounts {
+        perfect: counts[0],
+        great: counts[1],
+        good: counts[2],
+        miss: counts[3],
+    })
+}
+
+/// # 解析 BREAK 的判定
+///
+/// - 5 项: `50落,100落,GREAT,GOOD,MISS`,GREAT 按照 2000 分计算
+/// - 7 项: `50落,100落,GREAT 2000,GREAT 1500,GREAT 1250,GOOD,MISS`
+///
+/// 少于 5 项时与 5 项的格式相同,省略的项为 0
+pub fn parse_break_counts(value: &str) -> Result<BreakJudgeCounts, String> {
+    let size = match value.split(',').count() {
+        6 => return Err(format!("BREAK 判定[{}]应为 5 项或 7 项", value)),
+        7 => 7,
+        _ => 5,
+    };
+    let counts = parse_counts(value, size)?;
+    Ok(match counts.len() {
+        7 => BreakJudgeCounts {
+            perfect_high: counts[0],
+            perfect_low: counts[1],
+            great_high: counts[2],
+            great_mid: counts[3],
+            great_low: counts[4],
+            good: counts[5],
+            miss: counts[6],
+        },
+        _ => BreakJudgeCounts {
+            perfect_high: counts[0],
+            perfect_low: counts[1],
+            great_high: counts[2],
+            good: counts[3],
+            miss: counts[4],
+            ..Default::default()
+        },
+    })
+}
+
+/// 解析逗号分隔的数量,不足 `size` 项时在末尾补 0
+fn parse_counts(value: &str, size: usize) -> Result<Vec<u32>, String> {
+    let mut counts = value
+        .split(',')
+        .map(|count| {
+            count
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| format!("无法解析判定数量[{}]", count))
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    if counts.len() > size {
+        return Err(format!("判定[{}]最多只能有 {} 项", value, size));
+    }
+    counts.resize(size, 0);
+    Ok(counts)
+}
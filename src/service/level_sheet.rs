@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use image::imageops::{overlay, FilterType};
+use image::{DynamicImage, ImageError, ImageOutputFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_hollow_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use rusttype::Scale;
+
+use crate::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel};
+use crate::config::consts::{CONFIG_PATH, LAUNCH_PATH};
+use crate::config::theme::Theme;
+use crate::service::chart_index::ChartIndex;
+use crate::service::maimai_best_50::{draw_outlined_text, open_cover, DrawBest};
+use crate::utils::file::get_font;
+use crate::utils::image::encode_image;
+
+/// 封面边长
+const TILE_SIZE: u32 = 80;
+/// 封面之间的间距
+const TILE_GAP: i32 = 8;
+/// 每行的谱面数量
+const COLUMNS: usize = 10;
+/// 左侧定数标签的宽度
+const LABEL_WIDTH: i32 = 90;
+/// 边距
+const MARGIN: i32 = 24;
+/// 头部(标题与统计)的高度
+const HEADER_HEIGHT: i32 = 100;
+/// 不同定数之间的额外间距
+const SECTION_GAP: i32 = 12;
+
+/// # 谱面筛选条件
+///
+/// 按照等级(如 `13+`)或定数区间(如 `13.7..13.9`,包含两端)筛选
+#[derive(Clone, Debug)]
+pub enum LevelFilter {
+    Level(String),
+    Ds(f32, f32),
+}
+
+impl LevelFilter {
+    /// 谱面是否符合条件
+    pub fn matches(&self, level: &str, ds: f32) -> bool {
+        match self {
+            LevelFilter::Level(target) => level == target,
+            LevelFilter::Ds(from, to) => ds >= from - 0.001 && ds <= to + 0.001,
+        }
+    }
+
+    /// 图片标题中的描述
+    pub fn describe(&self) -> String {
+        match self {
+            LevelFilter::Level(level) => format!("Lv.{}", level),
+            LevelFilter::Ds(from, to) => format!("定数 {:.1} ~ {:.1}", from, to),
+        }
+    }
+}
+
+/// # 分组方式
+///
+/// 分数列表按照相邻谱面的定数或难度分组,左侧显示分组的标签
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SheetGroup {
+    /// 按照定数分组,标签为定数
+    Ds,
+    /// 按照难度分组,标签为难度名称
+    Level,
+}
+
+/// 分数列表中的单张谱面
+pub struct SheetChart {
+    pub song_id: i32,
+    pub title: String,
+    pub level_label: LevelLabel,
+    pub ds: f32,
+    /// 玩家成绩,未游玩时为空
+    pub score: Option<ChartInfoResponse>,
+}
+
+/// # 分数列表
+///
+/// 以封面网格的形式列出某个等级或定数区间内的全部谱面,按照定数从高到低分组,
+/// 每张谱面上标注玩家的评级与 FC/FS,未游玩的谱面显示为灰色
+pub struct DrawLevelSheet {
+    charts: Vec<SheetChart>,
+    /// 图片标题
+    title: String,
+    /// 默认保存的文件名
+    file_name: String,
+    group: SheetGroup,
+    /// 标题下方的统计信息,为空时统计评级与 FC 的数量
+    summary: Option<String>,
+    theme: Theme,
+    /// 图片目录
+    pic_dir: PathBuf,
+    /// 封面目录
+    cover_dir: PathBuf,
+}
+
+impl DrawLevelSheet {
+    /// # 初始化分数列表
+    ///
+    /// 从本地数据库中找出符合条件的谱面(不包含宴会场谱面),再与玩家成绩按照歌曲 ID 与难度匹配
+    pub fn new(
+        index: &ChartIndex,
+        filter: LevelFilter,
+        scores: Vec<ChartInfoResponse>,
+        nickname: &str,
+    ) -> Self {
+        let mut best_scores = best_scores(scores);
+        let mut charts: Vec<SheetChart> = index
+            .songs()
+            .filter(|song| !song.is_utage())
+            .flat_map(|song| {
+                song.level
+                    .iter()
+                    .zip(&song.ds)
+                    .enumerate()
+                    .filter(|(_, (level, ds))| filter.matches(level, **ds))
+                    .filter_map(|(index, (_, ds))| {
+                        let level_label = LevelLabel::from_index(index)?;
+                        Some(SheetChart {
+                            song_id: song.id as i32,
+                            title: song.title.clone(),
+                            level_label,
+                            ds: *ds,
+                            score: best_scores.remove(&(song.id as i32, level_label)),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        charts.sort_by(|a, b| {
+            b.ds.total_cmp(&a.ds)
+                .then(a.song_id.cmp(&b.song_id))
+                .then(a.level_label.cmp(&b.level_label))
+        });
+        let name = match &filter {
+            LevelFilter::Level(level) => level.clone(),
+            LevelFilter::Ds(from, to) => format!("{:.1}-{:.1}", from, to),
+        };
+        Self::from_charts(
+            charts,
+            &format!("{} 的 {} 分数列表", nickname, filter.describe()),
+            &format!("{}-{}-scores.png", nickname, name),
+        )
+    }
+
+    /// # 使用已经整理好的谱面初始化
+    ///
+    /// 谱面按照传入的顺序绘制,默认按照定数分组
+    pub fn from_charts(charts: Vec<SheetChart>, title: &str, file_name: &str) -> Self {
+        DrawLevelSheet {
+            charts,
+            title: title.to_string(),
+            file_name: file_name.to_string(),
+            group: SheetGroup::Ds,
+            summary: None,
+            theme: Theme::default(),
+            pic_dir: CONFIG_PATH.join("resource/mai/pic"),
+            cover_dir: CONFIG_PATH.join("resource/mai/cover"),
+        }
+    }
+
+    /// 设置分组方式
+    pub fn with_group(mut self, group: SheetGroup) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// 替换标题下方的统计信息
+    pub fn with_summary(mut self, summary: String) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+
+    /// 设置主题,使用其中的背景、字体与颜色
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// 列表内的谱面,按照定数从高到低排列
+    pub fn charts(&self) -> &[SheetChart] {
+        &self.charts
+    }
+
+    /// 默认的图片保存位置,在程序所在目录下
+    pub fn output_path(&self) -> PathBuf {
+        LAUNCH_PATH.join(&self.file_name)
+    }
+
+    /// 绘制并编码为指定格式
+    pub fn encode(&self, format: ImageOutputFormat) -> Result<Vec<u8>, ImageError> {
+        encode_image(self.render()?, format)
+    }
+
+    /// 按照分组方式把相邻的谱面分组,组内保持原有顺序
+    fn sections(&self) -> Vec<(String, &[SheetChart])> {
+        self.charts
+            .chunk_by(|a, b| match self.group {
+                SheetGroup::Ds => (a.ds * 10.0).round() == (b.ds * 10.0).round(),
+                SheetGroup::Level => a.level_label == b.level_label,
+            })
+            .map(|charts| {
+                let label = match self.group {
+                    SheetGroup::Ds => format!("{:.1}", charts[0].ds),
+                    SheetGroup::Level => charts[0].level_label.to_string(),
+                };
+                (label, charts)
+            })
+            .collect()
+    }
+
+    /// # 在内存中绘制图片
+    ///
+    /// 图片高度随谱面数量变化,背景使用主题中的背景图片
+    pub fn render(&self) -> Result<RgbaImage, ImageError> {
+        let sections = self.sections();
+        let tile_span = TILE_SIZE as i32 + TILE_GAP;
+        let width = (MARGIN * 2 + LABEL_WIDTH + tile_span * COLUMNS as i32 - TILE_GAP) as u32;
+        let grid_height: i32 = sections
+            .iter()
+            .map(|(_, charts)| charts.len().div_ceil(COLUMNS) as i32 * tile_span + SECTION_GAP)
+            .sum();
+        let height = (HEADER_HEIGHT + grid_height + MARGIN) as u32;
+        let background = image::open(self.pic_dir.join(&self.theme.background))?;
+        let mut img = background.resize_to_fill(width, height, FilterType::Lanczos3);
+
+        self.draw_header(&mut img);
+        let font = get_font(&self.theme.fonts.text);
+        // 难度名称较长,使用较小的字号
+        let (label_size, label_top) = match self.group {
+            SheetGroup::Ds => (28.0, 24),
+            SheetGroup::Level => (16.0, 32),
+        };
+        let mut top = HEADER_HEIGHT;
+        for (label, charts) in sections {
+            draw_text_mut(
+                &mut img,
+                Rgba(self.theme.colors.name),
+                MARGIN,
+                top + label_top,
+                Scale::uniform(label_size),
+                &font,
+                &label,
+            );
+            for (num, chart) in charts.iter().enumerate() {
+                let x = MARGIN + LABEL_WIDTH + tile_span * (num % COLUMNS) as i32;
+                let y = top + tile_span * (num / COLUMNS) as i32;
+                let tile = self.draw_tile(chart)?;
+                overlay(&mut img, &tile, x as i64, y as i64);
+            }
+            top += charts.len().div_ceil(COLUMNS) as i32 * tile_span + SECTION_GAP;
+        }
+        Ok(img.to_rgba8())
+    }
+
+    /// 绘制标题与游玩统计
+    fn draw_header(&self, img: &mut DynamicImage) {
+        let font = get_font(&self.theme.fonts.text);
+        draw_text_mut(
+            img,
+            Rgba(self.theme.colors.name),
+            MARGIN,
+            MARGIN,
+            Scale::uniform(30.0),
+            &font,
+            &self.title,
+        );
+        let scores: Vec<&ChartInfoResponse> = self
+            .charts
+            .iter()
+            .filter_map(|chart| chart.score.as_ref())
+            .collect();
+        let summary = self.summary.clone().unwrap_or_else(|| {
+            format!(
+                "已游玩 {} / {}    SSS+ {}    SSS 及以上 {}    FC 及以上 {}",
+                scores.len(),
+                self.charts.len(),
+                scores
+                    .iter()
+                    .filter(|score| score.rate == ChartRate::SSSP)
+                    .count(),
+                scores
+                    .iter()
+                    .filter(|score| score.rate >= ChartRate::SSS)
+                    .count(),
+                scores.iter().filter(|score| !score.fc.is_empty()).count(),
+            )
+        });
+        draw_text_mut(
+            img,
+            Rgba(self.theme.colors.author),
+            MARGIN,
+            MARGIN + 42,
+            Scale::uniform(18.0),
+            &font,
+            &summary,
+        );
+    }
+
+    /// # 绘制单张谱面
+    ///
+    /// 封面外框为难度颜色,已游玩的谱面在底部绘制评级与 FC/FS,左上角绘制达成率,未游玩的谱面显示为灰色
+    fn draw_tile(&self, chart: &SheetChart) -> Result<DynamicImage, ImageError> {
+        let cover = open_cover(&self.cover_dir, chart.song_id)?.resize_exact(
+            TILE_SIZE,
+            TILE_SIZE,
+            FilterType::Lanczos3,
+        );
+        let mut tile = match &chart.score {
+            Some(_) => cover,
+            None => DynamicImage::ImageRgba8(cover.grayscale().brighten(-40).to_rgba8()),
+        };
+
+        if let Some(score) = &chart.score {
+            let font = get_font(&self.theme.fonts.text);
+            let mut strip = RgbaImage::new(TILE_SIZE, 22);
+            draw_filled_rect_mut(
+                &mut strip,
+                Rect::at(0, 0).of_size(TILE_SIZE, 22),
+                Rgba(self.theme.colors.item_shadow),
+            );
+            overlay(&mut tile, &strip, 0, TILE_SIZE as i64 - 22);
+
+            let rank_img = image::open(self.pic_dir.join(score.rate.get_file_name()))?;
+            let rank_img = DrawBest::resize_pic(&rank_img, 42.0 / rank_img.width() as f32);
+            let rank_y = TILE_SIZE as i64 - 11 - rank_img.height() as i64 / 2;
+            overlay(&mut tile, &rank_img, 3, rank_y);
+            for (combo, x) in [(&score.fc, 46), (&score.fs, 62)] {
+                if combo.is_empty() {
+                    continue;
+                }
+                let icon = image::open(
+                    self.pic_dir
+                        .join(format!("UI_MSS_MBase_Icon_{}_S.png", combo)),
+                )?;
+                let icon = DrawBest::resize_pic(&icon, 16.0 / icon.height() as f32);
+                overlay(&mut tile, &icon, x, TILE_SIZE as i64 - 19);
+            }
+            draw_outlined_text(
+                &mut tile,
+                (
+                    Rgba(self.theme.colors.text),
+                    Rgba(self.theme.colors.outline),
+                ),
+                (4, 3),
+                Scale::uniform(13.0),
+                &font,
+                &format!("{:.2}", score.achievements),
+            );
+        }
+
+        // 难度颜色外框
+        let level_color = self.theme.level_color(chart.level_label);
+        for inset in 0..3 {
+            draw_hollow_rect_mut(
+                &mut tile,
+                Rect::at(inset, inset)
+                    .of_size(TILE_SIZE - 2 * inset as u32, TILE_SIZE - 2 * inset as u32),
+                level_color,
+            );
+        }
+        Ok(tile)
+    }
+}
+
+/// # 整理玩家成绩
+///
+/// 按照歌曲 ID 与难度建立索引,同一张谱面有多条成绩时取达成率最高的一条
+pub(crate) fn best_scores(
+    scores: Vec<ChartInfoResponse>,
+) -> HashMap<(i32, LevelLabel), ChartInfoResponse> {
+    let mut best_scores: HashMap<(i32, LevelLabel), ChartInfoResponse> = HashMap::new();
+    for chart in scores {
+        let key = (chart.song_id, chart.level_label);
+        match best_scores.get(&key) {
+            Some(best) if best.achievements >= chart.achievements => {}
+            _ => {
+                best_scores.insert(key, chart);
+            }
+        }
+    }
+    best_scores
+}
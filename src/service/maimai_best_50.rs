@@ -1,18 +1,29 @@
+use std::collections::HashMap;
 use std::ops::Index;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use clap::ValueEnum;
 use image::imageops::{overlay, FilterType};
-use image::{DynamicImage, ImageError, ImageFormat, Pixel, Rgba, RgbaImage};
-use imageproc::drawing::{draw_filled_rect_mut, draw_polygon_mut, draw_text_mut};
+use image::{DynamicImage, ImageError, ImageOutputFormat, Pixel, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_polygon_mut, draw_text_mut, text_size};
 use imageproc::map::map_colors_mut;
 use imageproc::point::Point;
 use imageproc::rect::Rect;
-use rusttype::Scale;
+use log::{error, info, warn};
+use rayon::prelude::*;
+use rusttype::{Font, Scale};
 
-use crate::clients::user_data::entity::{compute_ra, ChartInfoResponse};
+use crate::clients::user_data::entity::{
+    dx_star, ChartInfoResponse, Charts, LevelLabel, RatingRule,
+};
 use crate::config::consts::{CONFIG_PATH, LAUNCH_PATH};
-use crate::utils::file::{get_adobe_simhei_font, get_msyh_font};
-use crate::utils::image::{change_column_width, get_ra_pic, string_to_half_width};
+use crate::config::theme::Theme;
+use crate::service::asset_cache::AssetCache;
+use crate::service::chart_index::ChartIndex;
+use crate::service::plate::plate_id;
+use crate::utils::file::get_font;
+use crate::utils::image::{change_column_width, encode_image, get_ra_pic, string_to_half_width};
 
 const OFFSET: [(i32, i32); 8] = [
     (-1, -1),
@@ -24,11 +35,90 @@ const OFFSET: [(i32, i32); 8] = [
     (-1, 0),
     (1, 0),
 ];
-const COLUMNS_RATING: [i64; 5] = [84, 98, 113, 128, 143];
-const ITEM_WIDTH: i32 = 131;
-const ITEM_HEIGHT: i32 = 88;
-const VERTICAL_SPACING: i32 = 8;
-const HORIZONTAL_SPACING: i32 = 7;
+/// Rating 数字的横坐标,以缩放后(`RATING_SCALE`)的姓名牌为准
+const COLUMNS_RATING: [i32; 5] = [71, 83, 96, 109, 122];
+/// Rating 数字的纵坐标
+const ROW_RATING: i32 = 8;
+/// Rating 姓名牌相对素材的缩放比例
+const RATING_SCALE: f32 = 0.85;
+
+/// 歌曲封面的路径
+pub(crate) fn cover_path(cover_dir: &Path, song_id: i32) -> PathBuf {
+    cover_dir.join(format!("{:0>5}.png", song_id))
+}
+
+/// # 加载歌曲封面
+///
+/// 找不到封面时使用默认封面(`01000.png`)
+pub(crate) fn open_cover(cover_dir: &Path, song_id: i32) -> Result<DynamicImage, ImageError> {
+    match image::open(cover_path(cover_dir, song_id)) {
+        Ok(image) => Ok(image),
+        Err(_) => image::open(cover_dir.join("01000.png")),
+    }
+}
+
+/// # 绘制带描边的文字
+///
+/// 先在周围 8 个方向用描边颜色绘制一遍(硬核阴影),再用文字颜色绘制,`colors` 为 `(文字颜色, 描边颜色)`
+pub(crate) fn draw_outlined_text(
+    image: &mut DynamicImage,
+    (color, outline): (Rgba<u8>, Rgba<u8>),
+    (x, y): (i32, i32),
+    scale: Scale,
+    font: &Font,
+    text: &str,
+) {
+    OFFSET.iter().for_each(|(offset_x, offset_y)| {
+        draw_text_mut(
+            image,
+            outline,
+            x + offset_x,
+            y + offset_y,
+            scale,
+            font,
+            text,
+        );
+    });
+    draw_text_mut(image, color, x, y, scale, font, text);
+}
+
+/// # B50 图片布局
+///
+/// 决定 `(旧版本, 新版本)` 两个列表的大小,图片的网格行数会随之变化
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum BestLayout {
+    /// B25 + B15,DX 规则时期的布局
+    B40,
+    /// B35 + B15,当前的布局
+    B50,
+    /// 使用 `--sizes` 指定的列表大小
+    Custom,
+}
+
+impl BestLayout {
+    /// 布局对应的列表大小,自定义布局返回 `None`
+    pub fn sizes(&self) -> Option<(usize, usize)> {
+        match self {
+            BestLayout::B40 => Some((25, 15)),
+            BestLayout::B50 => Some((35, 15)),
+            BestLayout::Custom => None,
+        }
+    }
+}
+
+/// # 解析列表大小
+///
+/// 格式为 `旧版本,新版本`,例如 `25,15`,列表大小必须大于 0
+pub fn parse_best_sizes(sizes: &str) -> Result<(usize, usize), String> {
+    let (sd_size, dx_size) = sizes
+        .split_once(',')
+        .ok_or(format!("列表大小[{}]格式错误,正确格式如 35,15", sizes))?;
+    let parse = |size: &str| match size.trim().parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        _ => Err(format!("列表大小[{}]必须是大于 0 的整数", size)),
+    };
+    Ok((parse(sd_size)?, parse(dx_size)?))
+}
 
 /// # 自排序 Best 列表
 ///
@@ -37,23 +127,88 @@ const HORIZONTAL_SPACING: i32 = 7;
 /// - `BestList::new(15)` 可以创建一个大小为 15 的列表,用来装载 B15
 /// - `BestList::new(35)` 可以创建一个大小为 35 的列表,用来装载 B35
 ///
+/// 插入的谱面会按照配置文件中选择的 `RatingRule` 重新计算 Rating,排序、总分与底分都以此为准
+///
 /// > 这个结构体不知道要不要留,先按照 [mai-bot](https://github.com/Diving-Fish/mai-bot) 的规则来
 ///
 /// 毕竟*最大头的还是PIL库的调用*
+#[derive(Clone)]
 pub struct BestList {
     data: Vec<ChartInfoResponse>,
     size: usize,
+    rule: RatingRule,
 }
 
 impl BestList {
     pub fn new(size: usize) -> Self {
+        Self::with_rule(size, RatingRule::current())
+    }
+
+    /// 使用指定的 Rating 规则创建列表
+    pub fn with_rule(size: usize, rule: RatingRule) -> Self {
         Self {
             data: Vec::with_capacity(size),
             size,
+            rule,
+        }
+    }
+
+    /// 将查分器返回的谱面装入 `(旧版本, 新版本)` 大小的列表,返回 `(B35, B15)`
+    ///
+    /// 一般使用 Rating 规则对应的大小(`RatingRule::best_sizes`)
+    pub fn from_charts(
+        charts: Charts,
+        rule: RatingRule,
+        (sd_size, dx_size): (usize, usize),
+    ) -> (BestList, BestList) {
+        let mut sd_best_list = BestList::with_rule(sd_size, rule);
+        for chart in charts.sd {
+            sd_best_list.push(chart)
+        }
+        let mut dx_best_list = BestList::with_rule(dx_size, rule);
+        for chart in charts.dx {
+            dx_best_list.push(chart)
         }
+        (sd_best_list, dx_best_list)
     }
 
-    pub fn push(&mut self, elem: ChartInfoResponse) {
+    /// # 从全部成绩重建列表
+    ///
+    /// 不信任查分器的新旧版本划分,使用本地数据库判断谱面是否属于当前版本(见 `ChartIndex::is_new`),返回 `(B35, B15)`
+    ///
+    /// - 宴会场谱面不计入 Rating,直接跳过
+    /// - 本地数据库中找不到的谱面无法判断版本,给出警告后跳过
+    pub fn from_records(
+        records: Vec<ChartInfoResponse>,
+        index: &ChartIndex,
+        version: Option<&str>,
+        rule: RatingRule,
+        (sd_size, dx_size): (usize, usize),
+    ) -> (BestList, BestList) {
+        let mut sd_best_list = BestList::with_rule(sd_size, rule);
+        let mut dx_best_list = BestList::with_rule(dx_size, rule);
+        for chart in records {
+            match index.song(chart.song_id) {
+                Some(song) if song.is_utage() => continue,
+                Some(_) => {}
+                None => {
+                    warn!(
+                        "本地数据库中找不到歌曲[{}]{},无法判断版本,已跳过",
+                        chart.song_id, chart.title
+                    );
+                    continue;
+                }
+            }
+            match index.is_new(chart.song_id, version) {
+                Some(true) => dx_best_list.push(chart),
+                _ => sd_best_list.push(chart),
+            }
+        }
+        (sd_best_list, dx_best_list)
+    }
+
+    pub fn push(&mut self, mut elem: ChartInfoResponse) {
+        elem.ra = self.rule.chart_ra(&elem);
         if self.data.len() >= self.size && elem < *self.data.last().unwrap() {
             return;
         }
@@ -64,6 +219,36 @@ impl BestList {
     pub fn len(&self) -> usize {
         self.data.len()
     }
+
+    /// 列表的容量
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// 列表内的谱面迭代器,按照 Rating 从高到低排列
+    pub fn iter(&self) -> std::slice::Iter<'_, ChartInfoResponse> {
+        self.data.iter()
+    }
+
+    /// 列表内谱面的 Rating 总和
+    pub fn rating(&self) -> i32 {
+        self.data.iter().map(|chart| chart.ra).sum()
+    }
+
+    /// # 列表底分
+    ///
+    /// 进入这个列表至少需要的 Rating,列表没有装满的时候任何谱面都能进入,此时底分为 0
+    pub fn floor(&self) -> i32 {
+        match self.data.len() >= self.size {
+            true => self.data.last().map(|chart| chart.ra).unwrap_or(0),
+            false => 0,
+        }
+    }
+
+    /// 列表使用的 Rating 规则
+    pub fn rule(&self) -> RatingRule {
+        self.rule
+    }
 }
 
 impl Index<usize> for BestList {
@@ -94,25 +279,31 @@ pub struct DrawBest {
     pic_dir: PathBuf,
     /// 封面目录
     cover_dir: PathBuf,
-    /// 基底图片,可以理解为画布
+    /// 基底图片,可以理解为画布,绘制时根据主题的背景图片生成
     img: DynamicImage,
+    /// 绘制使用的主题
+    theme: Theme,
+    /// 缩放倍数,所有尺寸、字号与资源图片都按照这个倍数绘制
+    scale: f32,
+    /// 绘图资源缓存
+    cache: Arc<AssetCache>,
+    /// 画布上是否已经绘制完成
+    rendered: bool,
+    /// 姓名框背景(查分器中设置的牌子)
+    plate: Option<String>,
+    /// 段位
+    additional_rating: Option<i32>,
+    /// 列表内谱面的理论 DX 分数,用于绘制星级
+    max_dx_scores: HashMap<(i32, LevelLabel), i32>,
 }
 
 impl DrawBest {
     /// 初始化绘图
     pub fn new(sd_best: BestList, dx_best: BestList, username: &str) -> Self {
         // 计算标准谱面的 Rating
-        let sd_rating: i32 = sd_best
-            .data
-            .iter()
-            .map(|sd| compute_ra(sd.ds, sd.achievements))
-            .sum();
+        let sd_rating = sd_best.rating();
         // 计算 DX 谱面的 Rating
-        let dx_rating: i32 = dx_best
-            .data
-            .iter()
-            .map(|sd| compute_ra(sd.ds, sd.achievements))
-            .sum();
+        let dx_rating = dx_best.rating();
         DrawBest {
             sd_best,
             dx_best,
@@ -122,15 +313,117 @@ impl DrawBest {
             player_rating: sd_rating + dx_rating,
             pic_dir: CONFIG_PATH.join("resource/mai/pic"),
             cover_dir: CONFIG_PATH.join("resource/mai/cover"),
-            img: image::open(CONFIG_PATH.join("resource/mai/pic/UI_TTR_BG_Base_Plus.png")).unwrap(),
+            img: DynamicImage::new_rgba8(0, 0),
+            theme: Theme::default(),
+            scale: 1.0,
+            cache: Arc::new(AssetCache::new()),
+            rendered: false,
+            plate: None,
+            additional_rating: None,
+            max_dx_scores: HashMap::new(),
         }
     }
 
+    /// # 设置本地谱面索引
+    ///
+    /// 从本地数据库中获取列表内谱面的 Note 数量,用于计算 DX 分数星级,本地找不到的谱面不绘制星级
+    pub fn with_chart_index(mut self, index: &ChartIndex) -> Self {
+        self.max_dx_scores = self
+            .sd_best
+            .iter()
+            .chain(self.dx_best.iter())
+            .filter_map(|chart| {
+                let max_dx_score = index.max_dx_score(chart.song_id, chart.level_label)?;
+                Some(((chart.song_id, chart.level_label), max_dx_score))
+            })
+            .collect();
+        self
+    }
+
+    /// # 设置主题
+    ///
+    /// 默认使用原本的样式,配置文件夹下的主题需要通过 `Theme::load` 加载后传入
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// # 设置缩放倍数
+    ///
+    /// 用于输出 2 倍、3 倍的高清图片,资源图片直接从原图缩放到目标大小,不会先缩小再放大
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// # 设置资源缓存
+    ///
+    /// 多次绘制共用同一个缓存时,图标与封面只需要加载、缩放一次(例如在 Bot 中反复生成 B50)
+    pub fn with_cache(mut self, cache: Arc<AssetCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// # 设置牌子
+    ///
+    /// 对应 `B50Response::plate`,为空时不绘制
+    pub fn with_plate(mut self, plate: &str) -> Self {
+        self.plate = Some(plate.to_string()).filter(|plate| !plate.is_empty());
+        self
+    }
+
+    /// # 设置段位
+    ///
+    /// 对应 `B50Response::additional_rating`
+    pub fn with_dan(mut self, additional_rating: i32) -> Self {
+        self.additional_rating = Some(additional_rating);
+        self
+    }
+
+    /// # 加载可选的资源图片
+    ///
+    /// 依次尝试候选路径,返回第一张能打开的图片,全部失败时返回 `None`,由调用方决定如何回退
+    fn open_optional(candidates: &[PathBuf]) -> Option<DynamicImage> {
+        candidates.iter().find_map(|path| image::open(path).ok())
+    }
+
+    /// # 牌子图片
+    ///
+    /// 查分器返回的是牌子名称,依次查找:
+    ///
+    /// 1. `resource/mai/plate`目录下以牌子名称命名的图片,例如`舞舞舞.png`,可以用来补充或替换资源包中的牌子
+    /// 2. 资源包中对应 ID 的`UI_Plate_XXXXXX.png`(见 `plate_id`),牌子本身就是 ID 时直接使用
+    fn plate_image(&self) -> Option<DynamicImage> {
+        let plate = self.plate.as_ref()?;
+        let mut candidates = vec![CONFIG_PATH
+            .join("resource/mai/plate")
+            .join(format!("{}.png", plate))];
+        if let Some(id) = plate.parse::<u32>().ok().or_else(|| plate_id(plate)) {
+            candidates.push(self.pic_dir.join(format!("UI_Plate_{:0>6}.png", id)));
+        }
+        let image = Self::open_optional(&candidates);
+        if image.is_none() {
+            warn!("找不到牌子[{}]的图片,使用默认姓名框", plate);
+        }
+        image
+    }
+
+    /// # 段位图片
+    ///
+    /// 查分器的段位从 0(初心者)开始,依次为初段到十段、真初段到真十段、真皆传与里皆传,
+    /// 对应资源包中的`UI_DNM_DaniPlate_XX.png`
+    fn dan_image(&self) -> Option<DynamicImage> {
+        let additional_rating = self.additional_rating.filter(|rating| *rating >= 0)?;
+        Self::open_optional(&[self
+            .pic_dir
+            .join(format!("UI_DNM_DaniPlate_{:0>2}.png", additional_rating))])
+    }
+
     /// # 缩放图片
     ///
     /// 将大小不等的图片缩放指定的比例
     #[inline]
-    fn resize_pic(image: &DynamicImage, time: f32) -> DynamicImage {
+    pub(crate) fn resize_pic(image: &DynamicImage, time: f32) -> DynamicImage {
         image.resize(
             f32::floor(image.width() as f32 * time) as u32,
             f32::floor(image.height() as f32 * time) as u32,
@@ -138,78 +431,124 @@ impl DrawBest {
         )
     }
 
-    /// # 绘制 Rating 数字
+    /// 缩放图片,缩放比例会再乘以缩放倍数
+    fn scale_pic(&self, image: &DynamicImage, time: f32) -> DynamicImage {
+        Self::resize_pic(image, time * self.scale)
+    }
+
+    /// 按照缩放倍数换算长度
+    fn px(&self, length: i32) -> i32 {
+        (length as f32 * self.scale).round() as i32
+    }
+
+    /// 按照缩放倍数换算位置
+    fn point(&self, x: i64, y: i64) -> (i64, i64) {
+        (self.px(x as i32) as i64, self.px(y as i32) as i64)
+    }
+
+    /// 按照缩放倍数换算字号
+    fn font_size(&self, size: f32) -> Scale {
+        Scale::uniform(size * self.scale)
+    }
+
+    /// # Rating 姓名牌
     ///
-    /// 在图片上绘制 Rating 数字
-    fn draw_rating(&self, mut rating_base_img: DynamicImage) -> DynamicImage {
+    /// 姓名牌只缩放一次,数字直接从素材按照最终的大小缩放,避免高倍率下先缩小再放大导致模糊
+    pub fn rating_frame(&self) -> Result<DynamicImage, ImageError> {
+        let frame = image::open(
+            self.pic_dir
+                .join(get_ra_pic(self.player_rating as u32, self.sd_best.rule())),
+        )?;
+        let mut frame = self.scale_pic(&frame, RATING_SCALE);
         let num_str = self.player_rating.to_string();
         let digits: Vec<char> = num_str.chars().collect();
-        for (digit, index) in digits.iter().rev().zip(COLUMNS_RATING.iter().rev()) {
-            let mut digit_img =
-                image::open(self.pic_dir.join(format!("UI_NUM_Drating_{}.png", digit))).unwrap();
-            digit_img = Self::resize_pic(&digit_img, 0.6);
-            overlay(&mut rating_base_img, &digit_img, *index, 9);
+        for (digit, x) in digits.iter().rev().zip(COLUMNS_RATING.iter().rev()) {
+            let digit_img =
+                image::open(self.pic_dir.join(format!("UI_NUM_Drating_{}.png", digit)))?;
+            let digit_img = self.scale_pic(&digit_img, 0.6 * RATING_SCALE);
+            let (x, y) = (self.px(*x) as i64, self.px(ROW_RATING) as i64);
+            overlay(&mut frame, &digit_img, x, y);
         }
-        return rating_base_img;
+        Ok(frame)
     }
 
-    /// 绘制歌曲列表
-    fn draw_best_list(&mut self) -> Result<(), ImageError> {
-        // 绘制 b15 存在的图片列
-        for num in 0..self.dx_best.len() {
-            let column = 75i64
-                + (ITEM_WIDTH * (num % 3 + 7) as i32 + HORIZONTAL_SPACING * (num % 3) as i32)
-                    as i64;
-            let row = 120i64
-                + (ITEM_HEIGHT * (num / 3) as i32 + VERTICAL_SPACING * (num / 3) as i32) as i64;
-            // 3 列一行排列的 B15
-            let cover = self.draw_best_item(num, true)?;
-            // 绘制 item 的阴影,并把绘制完的 item 覆盖到最终输出里
-            self.draw_item_shadow_mut(column, row);
-            overlay(&mut self.img, &cover, column, row);
+    /// # 网格行数
+    ///
+    /// 默认旧版本列表每行 7 个,新版本列表每行 3 个,取两者中较多的行数
+    fn grid_rows(&self) -> usize {
+        let grid = &self.theme.grid;
+        self.sd_best
+            .size()
+            .div_ceil(grid.sd_columns)
+            .max(self.dx_best.size().div_ceil(grid.dx_columns))
+    }
+
+    /// # 准备画布
+    ///
+    /// 默认的背景图片正好放下 5 行网格,行数更多时纵向平铺背景,行数更少时裁掉多余的部分
+    fn canvas(&self, background: DynamicImage) -> DynamicImage {
+        let background = self.scale_pic(&background, 1.0);
+        let rows = self.grid_rows() as i32 - self.theme.background_rows as i32;
+        let height = background.height() as i32 + rows * self.px(self.theme.row_height());
+        if height <= background.height() as i32 {
+            return background.crop_imm(0, 0, background.width(), height as u32);
         }
+        let mut canvas = DynamicImage::new_rgba8(background.width(), height as u32);
+        for y in (0..height).step_by(background.height() as usize) {
+            overlay(&mut canvas, &background, 0, y as i64);
+        }
+        canvas
+    }
+
+    /// # 谱面元素的位置
+    ///
+    /// 默认旧版本列表 7 列一行排列在左侧,新版本列表 3 列一行排列在右侧,返回缩放后的位置
+    fn item_position(&self, num: usize, new: bool) -> (i64, i64) {
+        let grid = &self.theme.grid;
+        let (left, columns, offset) = match new {
+            true => (grid.dx_left, grid.dx_columns, grid.sd_columns),
+            false => (grid.sd_left, grid.sd_columns, 0),
+        };
+        let (row, column) = (num / columns, num % columns);
+        let x = left
+            + grid.item_width * (column + offset) as i32
+            + grid.horizontal_spacing * column as i32;
+        let y = grid.top + self.theme.row_height() * row as i32;
+        self.point(x as i64, y as i64)
+    }
 
-        // 绘制 b35 存在的图片列
-        for num in 0..self.sd_best.len() {
-            let column = 6i64
-                + (ITEM_WIDTH * (num % 7) as i32 + HORIZONTAL_SPACING * (num % 7) as i32) as i64;
-            let row = 120i64
-                + (ITEM_HEIGHT * (num / 7) as i32 + VERTICAL_SPACING * (num / 7) as i32) as i64;
-            // 7 列一行排列的 B35
-            let cover = self.draw_best_item(num, false)?;
+    /// # 绘制歌曲列表
+    ///
+    /// 谱面元素之间互不影响,先并行绘制全部元素,再依次覆盖到画布上
+    fn draw_best_list(&mut self) -> Result<(), ImageError> {
+        // b15 与 b35 存在的图片列
+        let items: Vec<(usize, bool)> = (0..self.dx_best.len())
+            .map(|num| (num, true))
+            .chain((0..self.sd_best.len()).map(|num| (num, false)))
+            .collect();
+        let covers = items
+            .par_iter()
+            .map(|(num, new)| self.draw_best_item(*num, *new))
+            .collect::<Result<Vec<_>, _>>()?;
+        for ((num, new), cover) in items.into_iter().zip(covers) {
+            let (column, row) = self.item_position(num, new);
             // 绘制 item 的阴影,并把绘制完的 item 覆盖到最终输出里
             self.draw_item_shadow_mut(column, row);
             overlay(&mut self.img, &cover, column, row);
         }
 
-        let mut blank_cover = image::open(self.cover_dir.join("01000.png"))?;
-        blank_cover =
-            Self::resize_pic(&blank_cover, ITEM_WIDTH as f32 / blank_cover.width() as f32);
-        blank_cover = blank_cover.crop_imm(
-            0,
-            (blank_cover.height() - ITEM_HEIGHT as u32) / 2,
-            ITEM_WIDTH as u32,
-            ITEM_HEIGHT as u32,
-        );
-        blank_cover = blank_cover.blur(3.0);
+        let blank_cover = self.cached_cover(1000)?;
         // 这里处理不完整的 b15 列表占位图
         for num in self.dx_best.len()..self.dx_best.size {
-            let column = 75i64
-                + (ITEM_WIDTH * (num % 3 + 7) as i32 + HORIZONTAL_SPACING * (num % 3) as i32)
-                    as i64;
-            let row = 120i64
-                + (ITEM_HEIGHT * (num / 3) as i32 + VERTICAL_SPACING * (num / 3) as i32) as i64;
+            let (column, row) = self.item_position(num, true);
             self.draw_item_shadow_mut(column, row);
-            overlay(&mut self.img, &blank_cover, column, row);
+            overlay(&mut self.img, &*blank_cover, column, row);
         }
         // 这里处理不完整的 b35 列表占位图
         for num in self.sd_best.len()..self.sd_best.size {
-            let column: i64 = 6i64
-                + (ITEM_WIDTH * (num % 7) as i32 + HORIZONTAL_SPACING * (num % 7) as i32) as i64;
-            let row: i64 = 120i64
-                + (ITEM_HEIGHT * (num / 7) as i32 + VERTICAL_SPACING * (num / 7) as i32) as i64;
+            let (column, row) = self.item_position(num, false);
             self.draw_item_shadow_mut(column, row);
-            overlay(&mut self.img, &blank_cover, column, row);
+            overlay(&mut self.img, &*blank_cover, column, row);
         }
         Ok(())
     }
@@ -217,161 +556,263 @@ impl DrawBest {
     /// # 绘制单个谱面元素
     ///
     /// - `new` 用于控制是绘制 B15 还是 B35 列表
-    fn draw_best_item(&mut self, num: usize, new: bool) -> Result<DynamicImage, ImageError> {
+    fn draw_best_item(&self, num: usize, new: bool) -> Result<DynamicImage, ImageError> {
         let chart = match new {
             true => self.dx_best.index(num),
             false => self.sd_best.index(num),
         };
+        let item_width = self.theme.grid.item_width;
+        let (triangle_right, triangle_size) = (self.px(item_width), self.px(27));
         let level_triangle = [
-            Point::new(ITEM_WIDTH, 0),
-            Point::new(ITEM_WIDTH - 27, 0),
-            Point::new(ITEM_WIDTH, 27),
+            Point::new(triangle_right, 0),
+            Point::new(triangle_right - triangle_size, 0),
+            Point::new(triangle_right, triangle_size),
         ];
-        let font = get_adobe_simhei_font();
+        let font = get_font(&self.theme.fonts.text);
+        let text_color = Rgba(self.theme.colors.text);
+        let brightness = self.theme.colors.cover_brightness;
 
-        // 获取歌曲封面
-        let mut cover = match image::open(self.cover_dir.join(format!("{:0>5}.png", chart.song_id)))
-        {
-            Ok(image) => image,
-            Err(_) => image::open(self.cover_dir.join("01000.png"))?,
-        };
-        cover = Self::resize_pic(&cover, ITEM_WIDTH as f32 / cover.width() as f32);
-        // 裁剪谱面图片,加上高斯模糊
-        cover = cover
-            .crop_imm(
-                0,
-                (cover.height() - ITEM_HEIGHT as u32) / 2,
-                ITEM_WIDTH as u32,
-                ITEM_HEIGHT as u32,
-            )
-            .blur(3.0);
+        // 获取裁剪、模糊后的歌曲封面
+        let mut cover = DynamicImage::clone(&*self.cached_cover(chart.song_id)?);
         // 谱面图片压暗
         map_colors_mut(&mut cover, |pixel| {
             let rgba = pixel.channels();
             Rgba([
-                (rgba[0] as f32 * 0.72).floor() as u8,
-                (rgba[1] as f32 * 0.72).floor() as u8,
-                (rgba[2] as f32 * 0.72).floor() as u8,
+                (rgba[0] as f32 * brightness).floor() as u8,
+                (rgba[1] as f32 * brightness).floor() as u8,
+                (rgba[2] as f32 * brightness).floor() as u8,
                 rgba[3],
             ])
         });
         // 在谱面右上角绘制等级定数小三角
-        draw_polygon_mut(&mut cover, &level_triangle, chart.level_label.label_color());
+        draw_polygon_mut(
+            &mut cover,
+            &level_triangle,
+            self.theme.level_color(chart.level_label),
+        );
 
         // 绘制谱面标题
         draw_text_mut(
             &mut cover,
-            Rgba([255, 255, 255, 255]),
-            8,
-            8,
-            Scale::uniform(16.0),
+            text_color,
+            self.px(8),
+            self.px(8),
+            self.font_size(16.0),
             &font,
-            change_column_width(&*chart.title, ITEM_WIDTH).as_str(),
+            change_column_width(&chart.title, item_width, &font).as_str(),
         );
 
         // 绘制达成率
         draw_text_mut(
             &mut cover,
-            Rgba([255, 255, 255, 255]),
-            7,
-            28,
-            Scale::uniform(12.0),
+            text_color,
+            self.px(7),
+            self.px(28),
+            self.font_size(12.0),
             &font,
             format!("{:.4}%", chart.achievements).as_str(),
         );
 
         // Rank 图片
-        let mut rank_img = image::open(self.pic_dir.join(chart.rate.get_file_name()))?;
-        rank_img = Self::resize_pic(&rank_img, 0.3);
-        overlay(&mut cover, &rank_img, 72, 28);
-
-        let mut blank_img = image::open(self.pic_dir.join(format!("UI_MSS_MBase_Icon_Blank.png")))?;
-        blank_img = Self::resize_pic(&blank_img, 0.48);
-        if !chart.fc.is_empty() {
-            let mut fc_img = image::open(
-                self.pic_dir
-                    .join(format!("UI_MSS_MBase_Icon_{}_S.png", chart.fc)),
-            )?;
-            fc_img = Self::resize_pic(&fc_img, 0.48);
-            overlay(&mut cover, &fc_img, 105, 60);
-        } else {
-            overlay(&mut cover, &blank_img, 105, 60);
-        }
-
-        if !chart.fs.is_empty() {
-            let mut fs_img = image::open(
-                self.pic_dir
-                    .join(format!("UI_MSS_MBase_Icon_{}_S.png", chart.fs)),
-            )?;
-            fs_img = Self::resize_pic(&fs_img, 0.48);
-            overlay(&mut cover, &fs_img, 80, 60);
-        } else {
-            overlay(&mut cover, &blank_img, 80, 60);
+        let rank_img = self.cached_pic(&chart.rate.get_file_name(), 0.3)?;
+        let (x, y) = self.point(72, 28);
+        overlay(&mut cover, &*rank_img, x, y);
+
+        // FC 与 FS 图标,没有时使用空白图标
+        for (combo, position) in [(&chart.fc, (105, 60)), (&chart.fs, (80, 60))] {
+            let icon = match combo.is_empty() {
+                true => "UI_MSS_MBase_Icon_Blank.png".to_string(),
+                false => format!("UI_MSS_MBase_Icon_{}_S.png", combo),
+            };
+            let icon_img = self.cached_pic(&icon, 0.48)?;
+            let (x, y) = self.point(position.0, position.1);
+            overlay(&mut cover, &*icon_img, x, y);
         }
 
         draw_text_mut(
             &mut cover,
-            Rgba([255, 255, 255, 255]),
-            8,
-            44,
-            Scale::uniform(12.0),
+            text_color,
+            self.px(8),
+            self.px(44),
+            self.font_size(12.0),
             &font,
-            format!(
-                "Base: {} -> {}",
-                chart.ds,
-                compute_ra(chart.ds, chart.achievements)
-            )
-            .as_str(),
+            format!("Base: {} -> {}", chart.ds, chart.ra).as_str(),
         );
+        let rank = format!("#{}", num + 1);
         draw_text_mut(
             &mut cover,
-            Rgba([255, 255, 255, 255]),
-            8,
-            60,
-            Scale::uniform(18.0),
+            text_color,
+            self.px(8),
+            self.px(60),
+            self.font_size(18.0),
             &font,
-            format!("#{}", num + 1).as_str(),
+            rank.as_str(),
         );
+
+        // DX 分数星级紧跟在排名后面,本地没有谱面数据或不足一星时不绘制
+        if let Some(max_dx_score) = self.max_dx_scores.get(&(chart.song_id, chart.level_label)) {
+            let star = dx_star(chart.dx_score, *max_dx_score);
+            if star > 0 {
+                let (rank_width, _) = text_size(self.font_size(18.0), &font, &rank);
+                draw_text_mut(
+                    &mut cover,
+                    Self::star_color(star),
+                    self.px(10) + rank_width,
+                    self.px(67),
+                    self.font_size(9.0),
+                    &font,
+                    &"★".repeat(star as usize),
+                );
+            }
+        }
         Ok(cover)
     }
 
+    /// 星级颜色,与游戏内相同:一、二星为绿色,三、四星为橙色,五星为金色
+    fn star_color(star: u8) -> Rgba<u8> {
+        match star {
+            1 | 2 => Rgba([96, 222, 128, 255]),
+            3 | 4 => Rgba([255, 168, 64, 255]),
+            _ => Rgba([255, 230, 80, 255]),
+        }
+    }
+
+    /// 从缓存中获取按缩放倍数缩放后的资源图片
+    fn cached_pic(&self, file_name: &str, time: f32) -> Result<Arc<DynamicImage>, ImageError> {
+        let path = self.pic_dir.join(file_name);
+        let key = format!("{}@{}", path.display(), time * self.scale);
+        self.cache
+            .get_or_load(key, || Ok(self.scale_pic(&image::open(&path)?, time)))
+    }
+
+    /// # 从缓存中获取歌曲封面
+    ///
+    /// 缓存的是裁剪并加上高斯模糊之后的封面,找不到封面时使用默认封面(`01000.png`)
+    fn cached_cover(&self, song_id: i32) -> Result<Arc<DynamicImage>, ImageError> {
+        let path = cover_path(&self.cover_dir, song_id);
+        let (width, height) = (
+            self.px(self.theme.grid.item_width),
+            self.px(self.theme.grid.item_height),
+        );
+        let key = format!("{}@{}x{}", path.display(), width, height);
+        self.cache.get_or_load(key, || {
+            let cover = open_cover(&self.cover_dir, song_id)?;
+            Ok(self.item_cover(cover).blur(3.0 * self.scale))
+        })
+    }
+
+    /// 将封面缩放到谱面元素的宽度,并居中裁剪到谱面元素的高度
+    fn item_cover(&self, cover: DynamicImage) -> DynamicImage {
+        let (width, height) = (
+            self.px(self.theme.grid.item_width) as u32,
+            self.px(self.theme.grid.item_height) as u32,
+        );
+        let cover = Self::resize_pic(&cover, width as f32 / cover.width() as f32);
+        cover.crop_imm(0, cover.height().saturating_sub(height) / 2, width, height)
+    }
+
     /// 绘制谱面元素下面的阴影
     fn draw_item_shadow_mut(&mut self, x: i64, y: i64) {
-        let mut mask = RgbaImage::new(ITEM_WIDTH as u32, ITEM_HEIGHT as u32);
+        let (width, height) = (
+            self.px(self.theme.grid.item_width) as u32,
+            self.px(self.theme.grid.item_height) as u32,
+        );
+        let mut mask = RgbaImage::new(width, height);
         draw_filled_rect_mut(
             &mut mask,
-            Rect::at(0, 0).of_size(ITEM_WIDTH as u32, ITEM_HEIGHT as u32),
-            Rgba([0, 0, 0, 150]),
+            Rect::at(0, 0).of_size(width, height),
+            Rgba(self.theme.colors.item_shadow),
         );
-        overlay(&mut self.img, &mask, x + 2, y + 2);
+        let offset = self.px(2) as i64;
+        overlay(&mut self.img, &mask, x + offset, y + offset);
     }
 
+    /// # 绘制并保存图片
+    ///
+    /// 以 PNG 格式保存到程序所在目录下(`{用户名}-b50.png`),并使用系统默认程序打开
     pub fn draw(&mut self) -> Result<(), ImageError> {
-        let font = get_adobe_simhei_font();
+        let path = self.output_path("png");
+        std::fs::write(&path, self.encode(ImageOutputFormat::Png)?)?;
+        info!("图片已保存:[{}]", path.display());
+        if let Err(error) = open::that(&path) {
+            error!("无法打开文件: {:?}", error);
+        }
+        Ok(())
+    }
+
+    /// 默认的图片保存位置,在程序所在目录下
+    pub fn output_path(&self, extension: &str) -> PathBuf {
+        LAUNCH_PATH.join(format!("{}-b50.{}", self.username, extension))
+    }
+
+    /// # 编码图片
+    ///
+    /// 绘制并编码为指定格式,返回编码后的字节(见 `encode_image`)
+    pub fn encode(&mut self, format: ImageOutputFormat) -> Result<Vec<u8>, ImageError> {
+        encode_image(self.render()?, format)
+    }
+
+    /// # 在内存中绘制图片
+    ///
+    /// 返回绘制完成的图片,不会写入任何文件,重复调用时直接返回已经绘制的结果
+    pub fn render(&mut self) -> Result<RgbaImage, ImageError> {
+        if !self.rendered {
+            self.draw_all()?;
+            self.rendered = true;
+        }
+        Ok(self.img.to_rgba8())
+    }
+
+    /// 绘制图片的全部内容
+    fn draw_all(&mut self) -> Result<(), ImageError> {
+        let theme = self.theme.clone();
+        let header = &theme.header;
+        let font = get_font(&theme.fonts.text);
+        // 背景,图片路径为绝对路径时 `join` 直接使用该路径
+        self.img = self.canvas(image::open(self.pic_dir.join(&theme.background))?);
+
         // Splash LOGO
-        let mut splash_logo =
-            image::open(self.pic_dir.join("UI_CMN_TabTitle_MaimaiTitle_Ver214.png"))?;
-        splash_logo = Self::resize_pic(&splash_logo, 0.65);
-        overlay(&mut self.img, &splash_logo, 10, 10);
+        let mut splash_logo = image::open(self.pic_dir.join(&theme.logo.file))?;
+        splash_logo = self.scale_pic(&splash_logo, theme.logo.scale);
+        let (x, y) = self.point(theme.logo.x, theme.logo.y);
+        overlay(&mut self.img, &splash_logo, x, y);
+
+        // 牌子作为头部的背景,找不到图片时不绘制
+        if let Some(plate_img) = self.plate_image() {
+            let plate_img = self.scale_pic(&plate_img, 580.0 / plate_img.width() as f32);
+            let height = plate_img.height().min(self.px(108) as u32);
+            let plate_img = plate_img.crop_imm(0, 0, plate_img.width(), height);
+            let (x, y) = self.point(header.plate.x, header.plate.y);
+            overlay(&mut self.img, &plate_img, x, y);
+        }
 
         // 绘制 Rating 数字
-        let mut rating_base_img =
-            image::open(self.pic_dir.join(get_ra_pic(self.player_rating as u32)))?;
-        rating_base_img = self.draw_rating(rating_base_img);
-        rating_base_img = Self::resize_pic(&rating_base_img, 0.85);
-        overlay(&mut self.img, &rating_base_img, 240, 8);
+        let rating_base_img = self.rating_frame()?;
+        let (rating_x, rating_y) = self.point(header.rating.x, header.rating.y);
+        overlay(&mut self.img, &rating_base_img, rating_x, rating_y);
+
+        // Rating 右侧的段位,找不到图片时不绘制
+        if let Some(dan_img) = self.dan_image() {
+            let dan_img = self.scale_pic(&dan_img, 30.0 / dan_img.height() as f32);
+            let x = rating_x + self.px(6) as i64 + rating_base_img.width() as i64;
+            overlay(&mut self.img, &dan_img, x, rating_y);
+        }
 
         // 绘制姓名列
         let mut name_plate_img = image::open(self.pic_dir.join("UI_TST_PlateMask.png"))?;
-        name_plate_img = name_plate_img.resize_exact(280, 40, FilterType::Lanczos3);
+        name_plate_img = name_plate_img.resize_exact(
+            self.px(280) as u32,
+            self.px(40) as u32,
+            FilterType::Lanczos3,
+        );
 
         draw_text_mut(
             &mut name_plate_img,
-            Rgba([0, 0, 0, 255]),
-            10,
-            4,
-            Scale::uniform(32.0),
-            &get_msyh_font(),
+            Rgba(theme.colors.name),
+            self.px(10),
+            self.px(4),
+            self.font_size(32.0),
+            &get_font(&theme.fonts.name),
             &self
                 .username
                 .chars()
@@ -379,87 +820,75 @@ impl DrawBest {
                 .collect::<String>(),
         );
         let mut name_dx_img = image::open(self.pic_dir.join("UI_CMN_Name_DX.png"))?;
-        name_dx_img = Self::resize_pic(&name_dx_img, 0.9);
+        name_dx_img = self.scale_pic(&name_dx_img, 0.9);
 
-        overlay(&mut name_plate_img, &name_dx_img, 220, 4);
-        overlay(&mut self.img, &name_plate_img, 240, 40);
+        let (x, y) = self.point(220, 4);
+        overlay(&mut name_plate_img, &name_dx_img, x, y);
+        let (x, y) = self.point(header.name.x, header.name.y);
+        overlay(&mut self.img, &name_plate_img, x, y);
 
         // 姓名列下面的 DX 分数计算列
         let mut shougou_img = image::open(self.pic_dir.join("UI_CMN_Shougou_Rainbow.png"))?;
+        shougou_img = self.scale_pic(&shougou_img, 1.0);
         let play_count_info = format!(
             "SD: {} + DX: {} = {}",
             self.sd_rating, self.dx_rating, self.player_rating
         );
-        // 硬核阴影绘制
-        OFFSET.iter().for_each(|(x, y)| {
-            draw_text_mut(
-                &mut shougou_img,
-                Rgba([50, 50, 50, 255]),
-                12 + x,
-                6 + y,
-                Scale::uniform(14.0),
-                &font,
-                &play_count_info,
-            );
-        });
-        draw_text_mut(
+        draw_outlined_text(
             &mut shougou_img,
-            Rgba([255, 255, 255, 255]),
-            12,
-            6,
-            Scale::uniform(14.0),
+            (Rgba(theme.colors.text), Rgba(theme.colors.outline)),
+            (self.px(12), self.px(6)),
+            self.font_size(14.0),
             &font,
             &play_count_info,
         );
 
         shougou_img = Self::resize_pic(&shougou_img, 1.05);
-        overlay(&mut self.img, &shougou_img, 240, 83);
+        let (x, y) = self.point(header.shougou.x, header.shougou.y);
+        overlay(&mut self.img, &shougou_img, x, y);
 
         // 最核心的 B50 绘制
         self.draw_best_list()?;
 
         // 右上角的 Generated By
         let mut author_board_img = image::open(self.pic_dir.join("UI_CMN_MiniDialog_01.png"))?;
-        author_board_img = Self::resize_pic(&author_board_img, 0.35);
+        author_board_img = self.scale_pic(&author_board_img, 0.35);
         draw_text_mut(
             &mut author_board_img,
-            Rgba([75, 75, 75, 255]),
-            31,
-            28,
-            Scale::uniform(15.0),
+            Rgba(theme.colors.author),
+            self.px(31),
+            self.px(28),
+            self.font_size(15.0),
             &font,
             "Generated By",
         );
         draw_text_mut(
             &mut author_board_img,
-            Rgba([75, 75, 75, 255]),
-            31,
-            50,
-            Scale::uniform(15.0),
+            Rgba(theme.colors.author),
+            self.px(31),
+            self.px(50),
+            self.font_size(15.0),
             &font,
             "Maimai-Search",
         );
-        overlay(&mut self.img, &author_board_img, 1224, 19);
+        let (x, y) = self.point(header.author.x, header.author.y);
+        overlay(&mut self.img, &author_board_img, x, y);
 
         // 新歌标签
-        overlay(
-            &mut self.img,
+        let new_label = self.scale_pic(
             &image::open(self.pic_dir.join("UI_RSL_MBase_Parts_01.png"))?,
-            988,
-            65,
+            1.0,
         );
+        let (x, y) = self.point(header.new_label.x, header.new_label.y);
+        overlay(&mut self.img, &new_label, x, y);
 
         // 标准标签
-        overlay(
-            &mut self.img,
+        let old_label = self.scale_pic(
             &image::open(self.pic_dir.join("UI_RSL_MBase_Parts_02.png"))?,
-            865,
-            65,
+            1.0,
         );
-
-        let path = LAUNCH_PATH.join(format!("{}-b50.png", self.username));
-        self.img.save_with_format(&path, ImageFormat::Png)?;
-        open::that(&path).unwrap();
+        let (x, y) = self.point(header.old_label.x, header.old_label.y);
+        overlay(&mut self.img, &old_label, x, y);
         Ok(())
     }
 }
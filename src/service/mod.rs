@@ -1,4 +1,19 @@
+pub mod asset_cache;
+pub mod best_export;
+pub mod calculator;
+pub mod chart_index;
+pub mod constant_table;
+pub mod history;
+pub mod judgement;
+pub mod level_sheet;
 pub mod maimai_best_50;
+pub mod planner;
+pub mod plate;
 pub mod printer;
+pub mod recommend;
 pub mod resource;
+pub mod score_push;
+pub mod simulator;
+pub mod song_card;
+pub mod stats;
 pub mod table;
@@ -0,0 +1,263 @@
+use std::cmp::Reverse;
+
+use prettytable::{row, Cell, Row, Table};
+
+use crate::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel, PUSH_RATES};
+use crate::config::consts::DIFFICULT_NAME;
+use crate::service::maimai_best_50::BestList;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// 新增谱面方案中最多考虑的谱面数量
+const MAX_NEW_CHARTS: usize = 3;
+
+/// # 方案中的单张谱面
+///
+/// 可以是 B50 中已有的谱面,也可以是一张假想的新谱面(此时没有难度与当前达成率)
+pub struct PlanStep {
+    /// 歌曲标题
+    pub title: String,
+    /// 歌曲类型
+    pub song_type: String,
+    /// 难度标签,新谱面为空
+    pub level_label: Option<LevelLabel>,
+    /// 谱面定数
+    pub ds: f32,
+    /// 当前达成率,新谱面为空
+    pub achievements: Option<f32>,
+    /// 目标评级
+    pub target_rate: ChartRate,
+    /// 当前 Rating,新谱面为被挤出列表的谱面 Rating
+    pub from_ra: i32,
+    /// 目标 Rating
+    pub to_ra: i32,
+}
+
+/// # 上分方案
+pub struct RatingPlan {
+    /// 方案描述
+    pub description: String,
+    /// 方案涉及的谱面
+    pub steps: Vec<PlanStep>,
+    /// 完成方案后的 Rating
+    pub rating: i32,
+}
+
+/// # 目标 Rating 规划
+///
+/// 给定目标 Rating,在最高定数与最高评级的限制下计算可行的上分方案
+///
+/// - 推分方案: 将 B50 中收益最高的若干谱面推到同一个评级
+/// - 新增方案: 在 B35 或 B15 中新增 1 ~ 3 张谱面,计算所需的最低定数
+///
+/// 所有方案都在 `BestList` 的副本上模拟插入,所以挤出底分谱面的情况会被正确计算
+pub struct RatingPlanner;
+
+impl RatingPlanner {
+    /// 计算全部可行方案,按照涉及的谱面数量从少到多排列
+    pub fn plan(
+        sd_best: &BestList,
+        dx_best: &BestList,
+        target: i32,
+        max_ds: f32,
+        max_rate: ChartRate,
+    ) -> Vec<RatingPlan> {
+        let mut plans = Vec::new();
+        for rate in PUSH_RATES.iter().filter(|rate| **rate <= max_rate) {
+            if let Some(plan) = Self::raise_plan(sd_best, dx_best, target, max_ds, *rate) {
+                plans.push(plan);
+            }
+        }
+        for count in 1..=MAX_NEW_CHARTS {
+            for new in [false, true] {
+                if let Some(plan) =
+                    Self::new_chart_plan(sd_best, dx_best, target, max_ds, max_rate, count, new)
+                {
+                    plans.push(plan);
+                }
+            }
+        }
+        plans.sort_by(|a, b| {
+            a.steps
+                .len()
+                .cmp(&b.steps.len())
+                .then_with(|| b.rating.cmp(&a.rating))
+        });
+        plans
+    }
+
+    /// # 推分方案
+    ///
+    /// 把 B50 中推到 `rate` 收益最高的谱面依次加入方案,直到达成目标
+    fn raise_plan(
+        sd_best: &BestList,
+        dx_best: &BestList,
+        target: i32,
+        max_ds: f32,
+        rate: ChartRate,
+    ) -> Option<RatingPlan> {
+        let rule = sd_best.rule();
+        let mut rating = sd_best.rating() + dx_best.rating();
+        let mut candidates: Vec<PlanStep> = sd_best
+            .iter()
+            .chain(dx_best.iter())
+            .filter(|chart| chart.ds <= max_ds && chart.achievements < rate.min_achievement())
+            .map(|chart| PlanStep {
+                title: chart.title.clone(),
+                song_type: chart.song_type.clone(),
+                level_label: Some(chart.level_label),
+                ds: chart.ds,
+                achievements: Some(chart.achievements),
+                target_rate: rate,
+                from_ra: chart.ra,
+                to_ra: rule.compute_ra(chart.ds, rate.min_achievement()),
+            })
+            .filter(|step| step.to_ra > step.from_ra)
+            .collect();
+        candidates.sort_by_key(|step| Reverse(step.to_ra - step.from_ra));
+
+        let mut steps = Vec::new();
+        for step in candidates {
+            if rating >= target {
+                break;
+            }
+            rating += step.to_ra - step.from_ra;
+            steps.push(step);
+        }
+        if rating < target || steps.is_empty() {
+            return None;
+        }
+        Some(RatingPlan {
+            description: format!("将 {} 张 B50 谱面推到 {}", steps.len(), rate),
+            steps,
+            rating,
+        })
+    }
+
+    /// # 新增方案
+    ///
+    /// 在 B15(`new` 为真)或 B35 中新增 `count` 张 `max_rate` 评级的谱面,
+    /// 从低到高查找能达成目标的最低定数
+    fn new_chart_plan(
+        sd_best: &BestList,
+        dx_best: &BestList,
+        target: i32,
+        max_ds: f32,
+        max_rate: ChartRate,
+        count: usize,
+        new: bool,
+    ) -> Option<RatingPlan> {
+        let (best, other) = match new {
+            true => (dx_best, sd_best),
+            false => (sd_best, dx_best),
+        };
+        let achievement = max_rate.min_achievement();
+        let rule = best.rule();
+        for ds in (10..=(max_ds * 10.0).round() as i32).map(|ds| ds as f32 / 10.0) {
+            let mut simulation = best.clone();
+            let mut steps = Vec::new();
+            for _ in 0..count {
+                let from_ra = simulation.floor();
+                simulation.push(ChartInfoResponse {
+                    achievements: achievement,
+                    ds,
+                    dx_score: 0,
+                    fc: String::new(),
+                    fs: String::new(),
+                    level: String::new(),
+                    level_label: LevelLabel::Master,
+                    ra: rule.compute_ra(ds, achievement),
+                    rate: max_rate,
+                    song_id: -1,
+                    title: "新谱面".to_string(),
+                    song_type: if new { "DX" } else { "SD" }.to_string(),
+                });
+                steps.push(PlanStep {
+                    title: "新谱面".to_string(),
+                    song_type: if new { "B15" } else { "B35" }.to_string(),
+                    level_label: None,
+                    ds,
+                    achievements: None,
+                    target_rate: max_rate,
+                    from_ra,
+                    to_ra: rule.compute_ra(ds, achievement),
+                });
+            }
+            let rating = simulation.rating() + other.rating();
+            if rating >= target {
+                return Some(RatingPlan {
+                    description: format!(
+                        "在 {} 中新增 {} 张定数 {} 以上的谱面并达到 {}",
+                        if new { "B15" } else { "B35" },
+                        count,
+                        ds,
+                        max_rate
+                    ),
+                    steps,
+                    rating,
+                });
+            }
+        }
+        None
+    }
+
+    /// 构建方案表格,第一张表为方案总览,其余为每个方案的详情
+    pub fn plan_tables(plans: &[RatingPlan], current: i32) -> Vec<SongTable> {
+        let mut table = Table::new();
+        table.set_titles(row!["序号", "方案", "谱面数", "Rating", "变化"]);
+        for (index, plan) in plans.iter().enumerate() {
+            table.add_row(row![
+                index + 1,
+                plan.description,
+                plan.steps.len(),
+                plan.rating,
+                format!("{:+}", plan.rating - current)
+            ]);
+        }
+        let mut table_vec = vec![SongTable {
+            info: format!("上分方案 (当前 Rating: {})", current),
+            table,
+            head: MarkdownFormat::H2,
+        }];
+
+        for (index, plan) in plans.iter().enumerate() {
+            let mut table = Table::new();
+            table.set_titles(row![
+                "乐曲标题",
+                "类型",
+                "难度",
+                "定数",
+                "达成率",
+                "目标",
+                "Rating"
+            ]);
+            for step in &plan.steps {
+                let mut row = Row::empty();
+                row.add_cell(Cell::new(&step.title));
+                row.add_cell(Cell::new(&step.song_type));
+                row.add_cell(match step.level_label {
+                    Some(level_label) => DIFFICULT_NAME[level_label as usize].clone(),
+                    None => Cell::new("-"),
+                });
+                row.add_cell(Cell::new(&step.ds.to_string()));
+                row.add_cell(Cell::new(&match step.achievements {
+                    Some(achievements) => format!("{:.4}%", achievements),
+                    None => "-".to_string(),
+                }));
+                row.add_cell(Cell::new(&step.target_rate.to_string()));
+                row.add_cell(Cell::new(&format!("{} -> {}", step.from_ra, step.to_ra)));
+                table.add_row(row);
+            }
+            table_vec.push(SongTable {
+                info: format!(
+                    "方案 {}: {} (Rating: {})",
+                    index + 1,
+                    plan.description,
+                    plan.rating
+                ),
+                table,
+                head: MarkdownFormat::H3,
+            });
+        }
+        table_vec
+    }
+}
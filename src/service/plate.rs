@@ -0,0 +1,423 @@
+use std::fmt;
+
+use clap::ValueEnum;
+use prettytable::{row, Cell, Row, Table};
+
+use crate::clients::user_data::entity::{ChartInfoResponse, LevelLabel};
+use crate::config::consts::DIFFICULT_NAME;
+use crate::service::chart_index::ChartIndex;
+use crate::service::level_sheet::{best_scores, DrawLevelSheet, SheetChart, SheetGroup};
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// 旧框(maimai ~ FiNALE)的全部版本,舞系牌子需要这些版本的全部谱面
+const OLD_VERSIONS: [&str; 13] = [
+    "maimai",
+    "maimai PLUS",
+    "maimai GreeN",
+    "maimai GreeN PLUS",
+    "maimai ORANGE",
+    "maimai ORANGE PLUS",
+    "maimai PiNK",
+    "maimai PiNK PLUS",
+    "maimai MURASAKi",
+    "maimai MURASAKi PLUS",
+    "maimai MiLK",
+    "MiLK PLUS",
+    "maimai FiNALE",
+];
+
+/// # 牌子的版本
+///
+/// 牌子名称的第一个字与对应的收录版本(`BasicInfo::from`),国服的熊与華都需要でらっくす与 PLUS 两个版本的谱面
+const PLATE_VERSIONS: [(&str, &[&str]); 23] = [
+    ("真", &["maimai", "maimai PLUS"]),
+    ("超", &["maimai GreeN"]),
+    ("檄", &["maimai GreeN PLUS"]),
+    ("橙", &["maimai ORANGE"]),
+    ("暁", &["maimai ORANGE PLUS"]),
+    ("桃", &["maimai PiNK"]),
+    ("櫻", &["maimai PiNK PLUS"]),
+    ("紫", &["maimai MURASAKi"]),
+    ("菫", &["maimai MURASAKi PLUS"]),
+    ("白", &["maimai MiLK"]),
+    ("雪", &["MiLK PLUS"]),
+    ("輝", &["maimai FiNALE"]),
+    ("熊", &["maimai でらっくす", "maimai でらっくす PLUS"]),
+    ("華", &["maimai でらっくす", "maimai でらっくす PLUS"]),
+    ("爽", &["maimai でらっくす Splash"]),
+    ("煌", &["maimai でらっくす Splash PLUS"]),
+    ("宙", &["maimai でらっくす UNiVERSE"]),
+    ("星", &["maimai でらっくす UNiVERSE PLUS"]),
+    ("祭", &["maimai でらっくす FESTiVAL"]),
+    ("祝", &["maimai でらっくす FESTiVAL PLUS"]),
+    ("双", &["maimai でらっくす BUDDiES"]),
+    ("宴", &["maimai でらっくす BUDDiES PLUS"]),
+    ("镜", &["maimai でらっくす PRiSM"]),
+];
+
+/// # 牌子图片的 ID
+///
+/// 旧框版本牌子在资源包中对应的`UI_Plate_XXXXXX.png`,按照游戏中的顺序编号,真系没有将牌;
+/// でらっくす之后的牌子不在表中,需要以牌子名称命名放在`resource/mai/plate`目录下
+const PLATE_IDS: [(&str, u32); 51] = [
+    ("真極", 6101),
+    ("真神", 6102),
+    ("真舞舞", 6103),
+    ("超極", 6104),
+    ("超将", 6105),
+    ("超神", 6106),
+    ("超舞舞", 6107),
+    ("檄極", 6108),
+    ("檄将", 6109),
+    ("檄神", 6110),
+    ("檄舞舞", 6111),
+    ("橙極", 6112),
+    ("橙将", 6113),
+    ("橙神", 6114),
+    ("橙舞舞", 6115),
+    ("暁極", 6116),
+    ("暁将", 6117),
+    ("暁神", 6118),
+    ("暁舞舞", 6119),
+    ("桃極", 6120),
+    ("桃将", 6121),
+    ("桃神", 6122),
+    ("桃舞舞", 6123),
+    ("櫻極", 6124),
+    ("櫻将", 6125),
+    ("櫻神", 6126),
+    ("櫻舞舞", 6127),
+    ("紫極", 6128),
+    ("紫将", 6129),
+    ("紫神", 6130),
+    ("紫舞舞", 6131),
+    ("菫極", 6132),
+    ("菫将", 6133),
+    ("菫神", 6134),
+    ("菫舞舞", 6135),
+    ("白極", 6136),
+    ("白将", 6137),
+    ("白神", 6138),
+    ("白舞舞", 6139),
+    ("雪極", 6140),
+    ("雪将", 6141),
+    ("雪神", 6142),
+    ("雪舞舞", 6143),
+    ("輝極", 6144),
+    ("輝将", 6145),
+    ("輝神", 6146),
+    ("輝舞舞", 6147),
+    ("舞極", 6148),
+    ("舞将", 6149),
+    ("舞神", 6150),
+    ("舞舞舞", 6151),
+];
+
+/// # 牌子的达成条件
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum PlateGoal {
+    /// 極:全部 FC
+    Ji,
+    /// 将:全部 SSS
+    Jiang,
+    /// 神:全部 AP
+    Shen,
+    /// 舞舞:全部 FDX
+    Wuwu,
+}
+
+impl fmt::Display for PlateGoal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PlateGoal::Ji => "極",
+                PlateGoal::Jiang => "将",
+                PlateGoal::Shen => "神",
+                PlateGoal::Wuwu => "舞舞",
+            }
+        )
+    }
+}
+
+impl PlateGoal {
+    /// 成绩是否满足条件
+    pub fn is_cleared(&self, chart: &ChartInfoResponse) -> bool {
+        match self {
+            PlateGoal::Ji => !chart.fc.is_empty(),
+            PlateGoal::Jiang => chart.achievements >= 100.0,
+            PlateGoal::Shen => matches!(chart.fc.as_str(), "ap" | "app"),
+            PlateGoal::Wuwu => matches!(chart.fs.as_str(), "fsd" | "fsdp"),
+        }
+    }
+
+    /// 从牌子名称的后缀解析,支持简体与繁体写法
+    fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "極" | "极" => Some(PlateGoal::Ji),
+            "将" | "將" => Some(PlateGoal::Jiang),
+            "神" => Some(PlateGoal::Shen),
+            "舞舞" => Some(PlateGoal::Wuwu),
+            _ => None,
+        }
+    }
+}
+
+/// # 目标牌子
+///
+/// 由若干个收录版本与达成条件组成
+#[derive(Clone, Debug)]
+pub struct PlateTarget {
+    /// 牌子名称,如`祭将`
+    pub name: String,
+    /// 需要完成的收录版本
+    pub versions: Vec<String>,
+    pub goal: PlateGoal,
+    /// 是否需要完成 Re:MASTER 谱面,只有舞系牌子需要
+    pub remaster: bool,
+}
+
+impl PlateTarget {
+    /// # 解析牌子名称
+    ///
+    /// 第一个字为版本,其余为达成条件,例如`祭将`、`熊極`、`舞舞舞`
+    pub fn parse(name: &str) -> Result<Self, String> {
+        let mut chars = name.chars();
+        let version = chars.next().map(String::from).unwrap_or_default();
+        let goal = PlateGoal::from_suffix(chars.as_str()).ok_or(format!(
+            "无法识别牌子[{}]的达成条件,可选值为 極/将/神/舞舞",
+            name
+        ))?;
+        let versions: Vec<String> = match version.as_str() {
+            "舞" => OLD_VERSIONS.iter().map(|from| from.to_string()).collect(),
+            _ => PLATE_VERSIONS
+                .iter()
+                .find(|(plate, _)| *plate == version)
+                .ok_or(format!("无法识别牌子[{}]的版本", name))?
+                .1
+                .iter()
+                .map(|from| from.to_string())
+                .collect(),
+        };
+        Ok(PlateTarget {
+            name: name.to_string(),
+            remaster: version == "舞",
+            versions,
+            goal,
+        })
+    }
+
+    /// 自定义的版本组合,不包含 Re:MASTER 谱面
+    pub fn custom(versions: Vec<String>, goal: PlateGoal) -> Self {
+        PlateTarget {
+            name: format!("{} {}", versions.join("、"), goal),
+            versions,
+            goal,
+            remaster: false,
+        }
+    }
+}
+
+/// # 牌子图片的 ID
+///
+/// 牌子名称的达成条件支持简体与繁体写法(如`舞极`与`舞極`),不在表中的牌子返回 `None`
+pub fn plate_id(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+    let version = chars.next()?;
+    let goal = PlateGoal::from_suffix(chars.as_str())?;
+    PLATE_IDS
+        .iter()
+        .find(|(plate, _)| {
+            let mut chars = plate.chars();
+            chars.next() == Some(version) && PlateGoal::from_suffix(chars.as_str()) == Some(goal)
+        })
+        .map(|(_, id)| *id)
+}
+
+/// 单个难度的完成情况
+pub struct LevelProgress {
+    pub level_label: LevelLabel,
+    pub cleared: usize,
+    pub total: usize,
+}
+
+/// # 牌子进度
+///
+/// 按照收录版本从本地数据库中找出牌子需要的全部谱面(不包含宴会场谱面),与玩家的全部成绩匹配
+pub struct PlateProgress {
+    pub target: PlateTarget,
+    /// 牌子需要的全部谱面,按照难度从高到低、定数从高到低排列
+    charts: Vec<SheetChart>,
+}
+
+impl PlateProgress {
+    pub fn new(index: &ChartIndex, target: PlateTarget, scores: Vec<ChartInfoResponse>) -> Self {
+        let mut best_scores = best_scores(scores);
+        let max_level = match target.remaster {
+            true => LevelLabel::ReMaster,
+            false => LevelLabel::Master,
+        };
+        let mut charts: Vec<SheetChart> = index
+            .songs()
+            .filter(|song| !song.is_utage() && target.versions.contains(&song.basic_info.from))
+            .flat_map(|song| {
+                song.ds
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, ds)| {
+                        let level_label = LevelLabel::from_index(index)?;
+                        if level_label > max_level {
+                            return None;
+                        }
+                        Some(SheetChart {
+                            song_id: song.id as i32,
+                            title: song.title.clone(),
+                            level_label,
+                            ds: *ds,
+                            score: best_scores.remove(&(song.id as i32, level_label)),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        charts.sort_by(|a, b| {
+            b.level_label
+                .cmp(&a.level_label)
+                .then(b.ds.total_cmp(&a.ds))
+                .then(a.song_id.cmp(&b.song_id))
+        });
+        PlateProgress { target, charts }
+    }
+
+    /// 牌子需要的全部谱面
+    pub fn charts(&self) -> &[SheetChart] {
+        &self.charts
+    }
+
+    /// 谱面是否已经满足条件
+    pub fn is_cleared(&self, chart: &SheetChart) -> bool {
+        chart
+            .score
+            .as_ref()
+            .is_some_and(|score| self.target.goal.is_cleared(score))
+    }
+
+    /// 尚未满足条件的谱面
+    pub fn remaining(&self) -> impl Iterator<Item = &SheetChart> {
+        self.charts.iter().filter(|chart| !self.is_cleared(chart))
+    }
+
+    /// 每个难度的完成情况,按照从低到高排列
+    pub fn levels(&self) -> Vec<LevelProgress> {
+        let mut levels: Vec<LevelProgress> = Vec::new();
+        for chart in self.charts.iter().rev() {
+            if levels.last().map(|level| level.level_label) != Some(chart.level_label) {
+                levels.push(LevelProgress {
+                    level_label: chart.level_label,
+                    cleared: 0,
+                    total: 0,
+                });
+            }
+            if let Some(level) = levels.last_mut() {
+                level.total += 1;
+                level.cleared += self.is_cleared(chart) as usize;
+            }
+        }
+        levels
+    }
+
+    /// 进度的文字描述,如`祭将 完成 120 / 200`
+    pub fn describe(&self) -> String {
+        let cleared = self.charts.len() - self.remaining().count();
+        format!(
+            "{} 完成 {} / {}",
+            self.target.name,
+            cleared,
+            self.charts.len()
+        )
+    }
+
+    /// 每个难度的完成情况表格
+    pub fn progress_table(&self) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row!["难度", "已完成", "总数", "剩余", "完成度"]);
+        for level in self.levels() {
+            let mut row = Row::empty();
+            row.add_cell(DIFFICULT_NAME[level.level_label as usize].clone());
+            row.add_cell(Cell::new(&level.cleared.to_string()));
+            row.add_cell(Cell::new(&level.total.to_string()));
+            row.add_cell(Cell::new(&(level.total - level.cleared).to_string()));
+            row.add_cell(Cell::new(&format!(
+                "{:.1}%",
+                level.cleared as f32 * 100.0 / level.total as f32
+            )));
+            table.add_row(row);
+        }
+        SongTable {
+            info: self.describe(),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+
+    /// 剩余谱面表格,按照难度从高到低、定数从高到低排列
+    pub fn remaining_table(&self) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row!["ID", "乐曲标题", "难度", "定数", "当前成绩"]);
+        for chart in self.remaining() {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&chart.song_id.to_string()));
+            row.add_cell(Cell::new(&chart.title));
+            row.add_cell(DIFFICULT_NAME[chart.level_label as usize].clone());
+            row.add_cell(Cell::new(&format!("{:.1}", chart.ds)));
+            row.add_cell(Cell::new(&match &chart.score {
+                Some(score) => [
+                    format!("{:.4}%", score.achievements),
+                    score.fc.clone(),
+                    score.fs.clone(),
+                ]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+                None => "未游玩".to_string(),
+            }));
+            table.add_row(row);
+        }
+        SongTable {
+            info: format!("{} 剩余谱面", self.target.name),
+            table,
+            head: MarkdownFormat::H3,
+        }
+    }
+
+    /// # 剩余谱面图片
+    ///
+    /// 使用分数列表的样式,按照难度分组绘制剩余的谱面
+    pub fn into_sheet(self, nickname: &str) -> DrawLevelSheet {
+        let summary = self
+            .levels()
+            .iter()
+            .rev()
+            .map(|level| format!("{} {}/{}", level.level_label, level.cleared, level.total))
+            .collect::<Vec<_>>()
+            .join("    ");
+        let title = format!("{} 的 {} 剩余谱面", nickname, self.target.name);
+        let file_name = format!("{}-{}-plate.png", nickname, self.target.name);
+        let goal = self.target.goal;
+        let charts = self
+            .charts
+            .into_iter()
+            .filter(|chart| {
+                !chart
+                    .score
+                    .as_ref()
+                    .is_some_and(|score| goal.is_cleared(score))
+            })
+            .collect();
+        DrawLevelSheet::from_charts(charts, &title, &file_name)
+            .with_group(SheetGroup::Level)
+            .with_summary(summary)
+    }
+}
@@ -38,6 +38,13 @@ impl PrinterHandler {
         ConsolePrinter::print_std(table_vec, false);
     }
 
+    /// 通用表格处理器,用于输出歌曲检索以外的表格(例如 Rating 历史)
+    ///
+    /// - `markdown` 为真时以 markdown 格式输出在命令行
+    pub fn table_handler(table_vec: Vec<SongTable>, markdown: bool) {
+        ConsolePrinter::print_std(table_vec, markdown);
+    }
+
     /// Markdown 格式处理器
     pub fn file_handler(
         songs: Vec<Song>,
@@ -56,7 +63,19 @@ impl PrinterHandler {
             true => TableService::get_songs_detail(songs, pic_colum, &output),
             false => TableService::get_songs(songs, pic_colum, &output, level),
         };
+        Self::table_file_handler(table_vec, output, add);
+    }
 
+    /// # 通用 Markdown 表格处理器
+    ///
+    /// - 指定了 `output` 时覆盖写入文件
+    /// - 指定了 `add` 时追加到文件尾部
+    /// - 都没有指定时以 markdown 格式输出在命令行
+    pub fn table_file_handler(
+        table_vec: Vec<SongTable>,
+        output: Option<String>,
+        add: Option<String>,
+    ) {
         // 输出到文件
         if let Some(filename) = output {
             FilePrinter::write_markdown_file(filename, table_vec);
@@ -0,0 +1,164 @@
+use prettytable::{row, Cell, Row, Table};
+
+use crate::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel};
+use crate::config::consts::DIFFICULT_NAME;
+use crate::service::chart_index::{ChartIndex, IndexedChart};
+use crate::service::level_sheet::LevelFilter;
+use crate::service::maimai_best_50::BestList;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// # 谱面筛选条件
+///
+/// 所有条件都按照本地数据库中的歌曲信息判断,没有指定的条件不做限制
+#[derive(Default)]
+pub struct ChartFilter {
+    /// 等级或定数区间
+    pub level: Option<LevelFilter>,
+    /// 分区,如 `POPS&アニメ`
+    pub genre: Option<String>,
+    /// 收录版本(`BasicInfo::from`),如 `maimai でらっくす FESTiVAL`
+    pub version: Option<String>,
+}
+
+impl ChartFilter {
+    /// 谱面是否符合全部条件
+    pub fn matches(&self, chart: &IndexedChart) -> bool {
+        let info = &chart.song.basic_info;
+        let level = chart
+            .song
+            .level
+            .get(chart.level_label as usize)
+            .map(String::as_str)
+            .unwrap_or_default();
+        self.level
+            .as_ref()
+            .is_none_or(|filter| filter.matches(level, chart.ds))
+            && self.genre.as_ref().is_none_or(|genre| &info.genre == genre)
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|version| &info.from == version)
+    }
+}
+
+/// # 推荐谱面
+///
+/// 玩家没有游玩过、以目标评级完成后能够进入 B35 或 B15 的谱面
+pub struct Recommendation {
+    pub song_id: i32,
+    pub title: String,
+    pub song_type: String,
+    pub level_label: LevelLabel,
+    pub level: String,
+    pub ds: f32,
+    /// 是否属于当前版本(进入 B15)
+    pub is_new: bool,
+    /// 以目标评级完成后的单曲 Rating
+    pub target_ra: i32,
+    /// 所在列表的底分
+    pub floor: i32,
+}
+
+impl Recommendation {
+    /// 替换掉列表底分带来的 Rating 收益
+    pub fn gain(&self) -> i32 {
+        self.target_ra - self.floor
+    }
+}
+
+/// # 未游玩谱面推荐
+///
+/// 将本地数据库与玩家成绩对比找出没有游玩过的谱面,按照目标评级计算单曲 Rating,
+/// 只保留高于所在列表(B35 或 B15)底分的谱面
+pub struct RecommendService;
+
+impl RecommendService {
+    /// # 计算推荐列表
+    ///
+    /// 新旧版本按照本地数据库的 `is_new` 判断,按照 Rating 收益从高到低排列,收益相同时定数低的在前
+    pub fn recommend(
+        index: &ChartIndex,
+        (sd_best, dx_best): (&BestList, &BestList),
+        played: &[ChartInfoResponse],
+        filter: &ChartFilter,
+        target_rate: ChartRate,
+    ) -> Vec<Recommendation> {
+        let rule = sd_best.rule();
+        let mut items: Vec<Recommendation> = index
+            .unplayed(played)
+            .into_iter()
+            .filter(|chart| filter.matches(chart))
+            .filter_map(|chart| {
+                let is_new = chart.song.basic_info.is_new;
+                let floor = match is_new {
+                    true => dx_best.floor(),
+                    false => sd_best.floor(),
+                };
+                let target_ra = rule.compute_ra(chart.ds, target_rate.min_achievement());
+                (target_ra > floor).then(|| Recommendation {
+                    song_id: chart.song.id as i32,
+                    title: chart.song.title.clone(),
+                    song_type: chart.song.song_type.clone(),
+                    level_label: chart.level_label,
+                    level: chart
+                        .song
+                        .level
+                        .get(chart.level_label as usize)
+                        .cloned()
+                        .unwrap_or_default(),
+                    ds: chart.ds,
+                    is_new,
+                    target_ra,
+                    floor,
+                })
+            })
+            .collect();
+        items.sort_by(|a, b| {
+            b.gain()
+                .cmp(&a.gain())
+                .then_with(|| a.ds.total_cmp(&b.ds))
+                .then_with(|| a.song_id.cmp(&b.song_id))
+        });
+        items
+    }
+
+    /// 构建推荐列表表格
+    pub fn recommend_table(items: &[Recommendation], target_rate: ChartRate) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "序号",
+            "ID",
+            "乐曲标题",
+            "类型",
+            "难度",
+            "等级",
+            "定数",
+            "列表",
+            "Rating",
+            "收益"
+        ]);
+        for (index, item) in items.iter().enumerate() {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&(index + 1).to_string()));
+            row.add_cell(Cell::new(&item.song_id.to_string()));
+            row.add_cell(Cell::new(&item.title));
+            row.add_cell(Cell::new(&item.song_type));
+            row.add_cell(DIFFICULT_NAME[item.level_label as usize].clone());
+            row.add_cell(Cell::new(&item.level));
+            row.add_cell(Cell::new(&item.ds.to_string()));
+            row.add_cell(Cell::new(if item.is_new {
+                "新版本"
+            } else {
+                "旧版本"
+            }));
+            row.add_cell(Cell::new(&format!("{} -> {}", item.floor, item.target_ra)));
+            row.add_cell(Cell::new(&format!("{:+}", item.gain())));
+            table.add_row(row);
+        }
+        SongTable {
+            info: format!("未游玩谱面推荐({})", target_rate),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+}
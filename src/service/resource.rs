@@ -64,7 +64,9 @@ pub fn update_resource(force: bool) {
     }
     fs::create_dir_all(resource_path.as_path()).unwrap();
 
-    extract_zip_archive(&mut zip, resource_path);
+    extract_zip_archive(&mut zip, resource_path.clone());
+    // 资源包中没有以名称命名的牌子,预先创建目录方便放入自定义的牌子图片
+    fs::create_dir_all(resource_path.join("mai/plate")).unwrap();
     info!("资源文件解压成功");
 }
 
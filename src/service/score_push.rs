@@ -0,0 +1,368 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::exit;
+
+use log::{error, info, warn};
+use prettytable::{row, Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::clients::song_data::entity::Song;
+use crate::clients::user_data::entity::{
+    ChartInfoResponse, ChartRate, LevelLabel, RatingRule, PUSH_RATES,
+};
+use crate::config::consts::{CONFIG_PATH, DIFFICULT_NAME};
+use crate::service::maimai_best_50::BestList;
+use crate::service::table::{MarkdownFormat, SongTable};
+use crate::utils::file::create_dir;
+
+/// # 推分条目
+///
+/// 记录一张谱面推到下一个评级后能带来的 Rating 收益
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PushItem {
+    /// 歌曲 ID
+    pub song_id: i32,
+    /// 歌曲标题
+    pub title: String,
+    /// 歌曲类型
+    pub song_type: String,
+    /// 难度标签
+    pub level_label: LevelLabel,
+    /// 谱面定数
+    pub ds: f32,
+    /// 当前达成率,不在 B50 中的谱面为空
+    pub achievements: Option<f32>,
+    /// 当前计入 Rating 的分数,不在 B50 中的谱面为所在列表的底分
+    pub current_ra: i32,
+    /// 目标评级
+    pub target_rate: ChartRate,
+    /// 达到目标评级后的单曲 Rating
+    pub target_ra: i32,
+    /// Rating 收益
+    pub gain: i32,
+    /// 是否已经完成(保存的推分列表使用)
+    pub done: bool,
+}
+
+impl PushItem {
+    /// 推分效率: 每一点定数带来的 Rating 收益,定数越低的谱面越容易推
+    pub fn efficiency(&self) -> f32 {
+        self.gain as f32 / self.ds
+    }
+}
+
+/// # 临近评级线的谱面
+///
+/// 达成率与下一个评级(S ~ SSS+)的差距在指定范围内的谱面
+pub struct NearItem {
+    pub song_id: i32,
+    pub title: String,
+    pub song_type: String,
+    pub level_label: LevelLabel,
+    pub ds: f32,
+    pub achievements: f32,
+    /// 下一个评级
+    pub target_rate: ChartRate,
+    /// 与下一个评级的差距
+    pub gap: f32,
+    /// 当前的单曲 Rating
+    pub current_ra: i32,
+    /// 达到下一个评级后的单曲 Rating
+    pub target_ra: i32,
+}
+
+impl NearItem {
+    /// 达到下一个评级后的单曲 Rating 收益
+    pub fn gain(&self) -> i32 {
+        self.target_ra - self.current_ra
+    }
+}
+
+/// # 推分列表
+///
+/// 根据用户的 B50 与本地歌曲数据库计算推分收益
+///
+/// - B50 内的谱面: 推到下一个评级(S ~ SSS+)时新旧 Rating 的差值
+/// - B50 外的谱面: 以能超过列表底分的最低评级计算,收益为新 Rating 与底分的差值
+///
+/// 最终按照推分效率(收益 / 定数)从高到低排列
+pub struct ScorePushService;
+
+impl ScorePushService {
+    /// 计算推分列表,`count` 为保留的条目数量
+    pub fn get_push_list(
+        sd_best: &BestList,
+        dx_best: &BestList,
+        songs: &[Song],
+        count: usize,
+    ) -> Vec<PushItem> {
+        let rule = sd_best.rule();
+        let mut items: Vec<PushItem> = sd_best
+            .iter()
+            .chain(dx_best.iter())
+            .filter_map(|chart| {
+                let target_rate = *PUSH_RATES
+                    .iter()
+                    .find(|rate| rate.min_achievement() > chart.achievements)?;
+                // 与列表中的 Rating 一样按照当前的 AP 状态计算,否则 PRiSM 规则下 AP 谱面的收益会少 1 分
+                let target_ra = rule.ra_with_fc(chart.ds, target_rate.min_achievement(), &chart.fc);
+                Some(PushItem {
+                    song_id: chart.song_id,
+                    title: chart.title.clone(),
+                    song_type: chart.song_type.clone(),
+                    level_label: chart.level_label,
+                    ds: chart.ds,
+                    achievements: Some(chart.achievements),
+                    current_ra: chart.ra,
+                    target_rate,
+                    target_ra,
+                    gain: target_ra - chart.ra,
+                    done: false,
+                })
+            })
+            .collect();
+
+        let in_best = |song_id: i32, level_label: LevelLabel| {
+            sd_best
+                .iter()
+                .chain(dx_best.iter())
+                .any(|chart| chart.song_id == song_id && chart.level_label == level_label)
+        };
+        for song in songs.iter().filter(|song| !song.is_utage()) {
+            let floor = match song.basic_info.is_new {
+                true => dx_best.floor(),
+                false => sd_best.floor(),
+            };
+            for (index, ds) in song.ds.iter().enumerate() {
+                let level_label = match LevelLabel::from_index(index) {
+                    Some(level_label) => level_label,
+                    None => continue,
+                };
+                if in_best(song.id as i32, level_label) {
+                    continue;
+                }
+                let target = PUSH_RATES
+                    .iter()
+                    .map(|rate| (*rate, rule.compute_ra(*ds, rate.min_achievement())))
+                    .find(|(_, ra)| *ra > floor);
+                if let Some((target_rate, target_ra)) = target {
+                    items.push(PushItem {
+                        song_id: song.id as i32,
+                        title: song.title.clone(),
+                        song_type: song.song_type.clone(),
+                        level_label,
+                        ds: *ds,
+                        achievements: None,
+                        current_ra: floor,
+                        target_rate,
+                        target_ra,
+                        gain: target_ra - floor,
+                        done: false,
+                    });
+                }
+            }
+        }
+
+        items.retain(|item| item.gain > 0);
+        items.sort_by(|a, b| {
+            b.efficiency()
+                .total_cmp(&a.efficiency())
+                .then_with(|| b.gain.cmp(&a.gain))
+        });
+        items.truncate(count);
+        items
+    }
+
+    /// 构建推分列表表格,`show_done` 控制是否显示完成状态列
+    pub fn push_table(items: &[PushItem], show_done: bool) -> SongTable {
+        let mut table = Table::new();
+        let mut title = row![
+            "序号",
+            "乐曲标题",
+            "类型",
+            "难度",
+            "定数",
+            "当前达成率",
+            "目标",
+            "Rating",
+            "收益",
+            "效率"
+        ];
+        if show_done {
+            title.add_cell(Cell::new("完成"));
+        }
+        table.set_titles(title);
+        for (index, item) in items.iter().enumerate() {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&(index + 1).to_string()));
+            row.add_cell(Cell::new(&item.title));
+            row.add_cell(Cell::new(&item.song_type));
+            row.add_cell(DIFFICULT_NAME[item.level_label as usize].clone());
+            row.add_cell(Cell::new(&item.ds.to_string()));
+            row.add_cell(Cell::new(&match item.achievements {
+                Some(achievements) => format!("{:.4}%", achievements),
+                None => "-".to_string(),
+            }));
+            row.add_cell(Cell::new(&item.target_rate.to_string()));
+            row.add_cell(Cell::new(&format!(
+                "{} -> {}",
+                item.current_ra, item.target_ra
+            )));
+            row.add_cell(Cell::new(&format!("{:+}", item.gain)));
+            row.add_cell(Cell::new(&format!("{:.2}", item.efficiency())));
+            if show_done {
+                row.add_cell(Cell::new(if item.done { "✔" } else { "" }));
+            }
+            table.add_row(row);
+        }
+        SongTable {
+            info: "推分列表".to_string(),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+
+    /// 保存推分列表,覆盖之前保存的列表
+    pub fn save(username: &str, items: &[PushItem]) {
+        create_dir(&CONFIG_PATH.join("push"));
+        let path = Self::push_path(username);
+        let json = serde_json::to_string(items).unwrap();
+        match fs::write(&path, json) {
+            Ok(_) => info!("推分列表已保存:[{}]", path.display()),
+            Err(error) => {
+                error!("保存推分列表失败\n[Cause]:{:?}", error);
+                exit(exitcode::IOERR)
+            }
+        }
+    }
+
+    /// 读取保存的推分列表
+    pub fn load(username: &str) -> Vec<PushItem> {
+        let path = Self::push_path(username);
+        if !path.exists() {
+            warn!(
+                "用户[{}]还没有保存的推分列表,请使用 push --save 保存",
+                username
+            );
+            exit(exitcode::OK)
+        }
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(error) => {
+                error!("读取推分列表失败\n[Cause]:{:?}", error);
+                exit(exitcode::IOERR)
+            }
+        };
+        serde_json::from_str(&json).unwrap_or_else(|error| {
+            error!("解析推分列表失败\n[Cause]:{:?}", error);
+            exit(exitcode::DATAERR)
+        })
+    }
+
+    /// # 勾选推分条目
+    ///
+    /// `indexes` 为推分列表中的序号(从 1 开始),再次勾选会取消完成状态
+    pub fn toggle_done(username: &str, indexes: &[usize]) -> Vec<PushItem> {
+        let mut items = Self::load(username);
+        for index in indexes {
+            match index.checked_sub(1).and_then(|index| items.get_mut(index)) {
+                Some(item) => item.done = !item.done,
+                None => {
+                    error!(
+                        "推分条目序号[{}]不存在,可用的序号为 1 ~ {}",
+                        index,
+                        items.len()
+                    );
+                    exit(exitcode::USAGE)
+                }
+            }
+        }
+        Self::save(username, &items);
+        items
+    }
+
+    /// # 查找临近评级线的谱面
+    ///
+    /// 找出达成率距离下一个评级不超过 `margin`(百分点)的谱面,单曲 Rating 按照当前规则重新计算(包含 AP 奖励),
+    /// 按照达到评级后的 Rating 收益从高到低排列,收益相同时差距小的在前
+    pub fn near_threshold(
+        charts: &[ChartInfoResponse],
+        margin: f32,
+        rule: RatingRule,
+    ) -> Vec<NearItem> {
+        let mut items: Vec<NearItem> = charts
+            .iter()
+            .filter_map(|chart| {
+                let target_rate = *PUSH_RATES
+                    .iter()
+                    .find(|rate| rate.min_achievement() > chart.achievements)?;
+                let gap = target_rate.min_achievement() - chart.achievements;
+                // 浮点误差会让 0.5 的差距略大于 0.5
+                if gap > margin + 0.00001 {
+                    return None;
+                }
+                Some(NearItem {
+                    song_id: chart.song_id,
+                    title: chart.title.clone(),
+                    song_type: chart.song_type.clone(),
+                    level_label: chart.level_label,
+                    ds: chart.ds,
+                    achievements: chart.achievements,
+                    target_rate,
+                    gap,
+                    current_ra: rule.chart_ra(chart),
+                    target_ra: rule.ra_with_fc(chart.ds, target_rate.min_achievement(), &chart.fc),
+                })
+            })
+            .collect();
+        items.sort_by(|a, b| {
+            b.gain()
+                .cmp(&a.gain())
+                .then_with(|| a.gap.total_cmp(&b.gap))
+        });
+        items
+    }
+
+    /// 构建临近评级线的谱面表格
+    pub fn near_table(items: &[NearItem], margin: f32) -> SongTable {
+        let mut table = Table::new();
+        table.set_titles(row![
+            "序号",
+            "乐曲标题",
+            "类型",
+            "难度",
+            "定数",
+            "当前达成率",
+            "目标",
+            "差距",
+            "Rating",
+            "收益"
+        ]);
+        for (index, item) in items.iter().enumerate() {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&(index + 1).to_string()));
+            row.add_cell(Cell::new(&item.title));
+            row.add_cell(Cell::new(&item.song_type));
+            row.add_cell(DIFFICULT_NAME[item.level_label as usize].clone());
+            row.add_cell(Cell::new(&item.ds.to_string()));
+            row.add_cell(Cell::new(&format!("{:.4}%", item.achievements)));
+            row.add_cell(Cell::new(&item.target_rate.to_string()));
+            row.add_cell(Cell::new(&format!("{:.4}%", item.gap)));
+            row.add_cell(Cell::new(&format!(
+                "{} -> {}",
+                item.current_ra, item.target_ra
+            )));
+            row.add_cell(Cell::new(&format!("{:+}", item.gain())));
+            table.add_row(row);
+        }
+        SongTable {
+            info: format!("距离下一个评级 {}% 以内的谱面", margin),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+
+    fn push_path(username: &str) -> PathBuf {
+        CONFIG_PATH.join("push").join(format!("{}.json", username))
+    }
+}
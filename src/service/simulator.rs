@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use log::warn;
+use prettytable::{row, Table};
+
+use crate::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel};
+use crate::service::chart_index::ChartIndex;
+use crate::service::maimai_best_50::BestList;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// # 假设的成绩
+///
+/// 命令行中的格式为 `歌曲 ID:难度:达成率`,例如 `834:master:100.5`
+#[derive(Clone)]
+pub struct SimulatedChart {
+    pub song_id: i32,
+    pub level_label: LevelLabel,
+    pub achievements: f32,
+}
+
+/// # 解析假设的成绩
+///
+/// 难度可以是难度名称(`basic`、`master`、`remaster`、`re:master` 等,不区分大小写)或者下标(0 ~ 4)
+pub fn parse_simulated_chart(value: &str) -> Result<SimulatedChart, String> {
+    let format_error = || format!("成绩[{}]格式错误,正确格式如 834:master:100.5", value);
+    // 难度可能写作 re:master,所以只按照第一个与最后一个冒号拆分
+    let (song_id, rest) = value.split_once(':').ok_or_else(format_error)?;
+    let (level, achievements) = rest.rsplit_once(':').ok_or_else(format_error)?;
+    let song_id = song_id.trim().parse::<i32>().map_err(|_| format_error())?;
+    let level = level.trim();
+    let level_label = match level.parse::<usize>() {
+        Ok(index) => LevelLabel::from_index(index),
+        Err(_) => match level.to_lowercase().as_str() {
+            "remaster" | "re:master" => Some(LevelLabel::ReMaster),
+            level => LevelLabel::from_str(level, true).ok(),
+        },
+    }
+    .ok_or(format!("无法识别难度[{}]", level))?;
+    let achievements = achievements
+        .trim()
+        .trim_end_matches('%')
+        .parse::<f32>()
+        .map_err(|_| format_error())?;
+    if !(0.0..=101.0).contains(&achievements) {
+        return Err(format!("达成率[{}]超出范围", achievements));
+    }
+    Ok(SimulatedChart {
+        song_id,
+        level_label,
+        achievements,
+    })
+}
+
+/// # Rating 模拟器
+///
+/// 把假设的成绩叠加到当前成绩上,重新计算 Best 列表,与当前的列表对比
+pub struct SimulateService;
+
+impl SimulateService {
+    /// # 叠加假设的成绩
+    ///
+    /// - 已有成绩的谱面: 假设的达成率更高时替换,否则保持不变(与游戏中只保留最好成绩一致)
+    /// - 没有成绩的谱面: 使用本地数据库中的歌曲信息与定数新建一条成绩
+    ///
+    /// 本地数据库中找不到对应谱面时返回错误
+    pub fn apply(
+        charts: &mut Vec<ChartInfoResponse>,
+        index: &ChartIndex,
+        simulated: &[SimulatedChart],
+    ) -> Result<(), String> {
+        for result in simulated {
+            let rate = ChartRate::from_achievement(result.achievements);
+            let existing = charts.iter_mut().find(|chart| {
+                chart.song_id == result.song_id && chart.level_label == result.level_label
+            });
+            match existing {
+                Some(chart) if chart.achievements >= result.achievements => warn!(
+                    "[{}]{} 的假设成绩 {:.4}% 不高于当前成绩 {:.4}%,保持不变",
+                    chart.title, chart.level_label, result.achievements, chart.achievements
+                ),
+                Some(chart) => {
+                    chart.achievements = result.achievements;
+                    chart.rate = rate;
+                }
+                None => {
+                    let song = index.song(result.song_id).ok_or(format!(
+                        "本地数据库中找不到 ID 为 {} 的歌曲",
+                        result.song_id
+                    ))?;
+                    let level_index = result.level_label as usize;
+                    let (Some(ds), Some(level)) =
+                        (song.ds.get(level_index), song.level.get(level_index))
+                    else {
+                        return Err(format!(
+                            "歌曲[{}]{} 没有 {} 难度",
+                            song.id, song.title, result.level_label
+                        ));
+                    };
+                    charts.push(ChartInfoResponse {
+                        achievements: result.achievements,
+                        ds: *ds,
+                        dx_score: 0,
+                        fc: String::new(),
+                        fs: String::new(),
+                        level: level.clone(),
+                        level_label: result.level_label,
+                        ra: 0,
+                        rate,
+                        song_id: result.song_id,
+                        title: song.title.clone(),
+                        song_type: song.song_type.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// # 构建模拟后的 Best 列表表格
+    ///
+    /// 列出模拟后的完整列表,新进入与成绩提升的谱面标注状态,掉出列表的谱面放在表格末尾
+    pub fn list_table(info: &str, before: &BestList, after: &BestList) -> SongTable {
+        let key = |chart: &ChartInfoResponse| (chart.song_id, chart.level_label);
+        let before_map: HashMap<(i32, LevelLabel), &ChartInfoResponse> =
+            before.iter().map(|chart| (key(chart), chart)).collect();
+        let after_map: HashMap<(i32, LevelLabel), &ChartInfoResponse> =
+            after.iter().map(|chart| (key(chart), chart)).collect();
+
+        let mut table = Table::new();
+        table.set_titles(row![
+            "排名",
+            "状态",
+            "乐曲标题",
+            "类型",
+            "难度",
+            "定数",
+            "达成率",
+            "Rating"
+        ]);
+        for (rank, chart) in after.iter().enumerate() {
+            let (state, achievements, ra) = match before_map.get(&key(chart)) {
+                None => (
+                    "新进入",
+                    format!("{:.4}%", chart.achievements),
+                    chart.ra.to_string(),
+                ),
+                Some(old) if old.ra != chart.ra => (
+                    "提升",
+                    format!("{:.4}% -> {:.4}%", old.achievements, chart.achievements),
+                    format!("{} -> {}", old.ra, chart.ra),
+                ),
+                Some(_) => (
+                    "",
+                    format!("{:.4}%", chart.achievements),
+                    chart.ra.to_string(),
+                ),
+            };
+            table.add_row(row![
+                rank + 1,
+                state,
+                chart.title,
+                chart.song_type,
+                chart.level_label,
+                chart.ds,
+                achievements,
+                ra
+            ]);
+        }
+        for chart in before
+            .iter()
+            .filter(|chart| !after_map.contains_key(&key(chart)))
+        {
+            table.add_row(row![
+                "-",
+                "掉出",
+                chart.title,
+                chart.song_type,
+                chart.level_label,
+                chart.ds,
+                format!("{:.4}%", chart.achievements),
+                chart.ra
+            ]);
+        }
+        SongTable {
+            info: format!(
+                "{}: {} -> {} ({:+})",
+                info,
+                before.rating(),
+                after.rating(),
+                after.rating() - before.rating()
+            ),
+            table,
+            head: MarkdownFormat::H3,
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use image::imageops::{overlay, FilterType};
+use image::{DynamicImage, ImageError, ImageOutputFormat, Rgba, RgbaImage};
+use imageproc::drawing::{draw_filled_rect_mut, draw_text_mut};
+use imageproc::rect::Rect;
+use rusttype::Scale;
+
+use crate::clients::song_data::entity::Song;
+use crate::clients::user_data::entity::{ChartInfoResponse, LevelLabel};
+use crate::config::consts::{CONFIG_PATH, LAUNCH_PATH};
+use crate::config::theme::Theme;
+use crate::service::maimai_best_50::{draw_outlined_text, open_cover, DrawBest};
+use crate::utils::file::get_font;
+use crate::utils::image::{change_column_width, encode_image};
+
+/// 图片宽度
+const CARD_WIDTH: u32 = 800;
+/// 头部(封面与歌曲信息)的高度
+const HEADER_HEIGHT: i32 = 252;
+/// 每个难度占用的高度
+const ROW_HEIGHT: i32 = 84;
+/// 左右边距
+const MARGIN: i32 = 24;
+/// 封面边长
+const COVER_SIZE: u32 = 200;
+/// 成绩区域的左边界
+const SCORE_LEFT: i32 = 580;
+
+/// # 歌曲信息卡片
+///
+/// 绘制单首歌曲的封面、基本信息与每个难度的谱面信息,传入玩家成绩时在每个难度的右侧绘制达成率、评级、FC/FS 与 Rating
+pub struct DrawSongCard {
+    song: Song,
+    /// 玩家在各个难度上的成绩,`None` 表示不绘制成绩
+    scores: Option<HashMap<LevelLabel, ChartInfoResponse>>,
+    theme: Theme,
+    /// 图片目录
+    pic_dir: PathBuf,
+    /// 封面目录
+    cover_dir: PathBuf,
+}
+
+impl DrawSongCard {
+    pub fn new(song: Song) -> Self {
+        DrawSongCard {
+            song,
+            scores: None,
+            theme: Theme::default(),
+            pic_dir: CONFIG_PATH.join("resource/mai/pic"),
+            cover_dir: CONFIG_PATH.join("resource/mai/cover"),
+        }
+    }
+
+    /// # 设置玩家成绩
+    ///
+    /// 只保留这首歌的成绩,同一难度有多条成绩时取达成率最高的一条
+    pub fn with_scores(mut self, charts: Vec<ChartInfoResponse>) -> Self {
+        let mut scores: HashMap<LevelLabel, ChartInfoResponse> = HashMap::new();
+        for chart in charts
+            .into_iter()
+            .filter(|chart| chart.song_id as usize == self.song.id)
+        {
+            match scores.get(&chart.level_label) {
+                Some(best) if best.achievements >= chart.achievements => {}
+                _ => {
+                    scores.insert(chart.level_label, chart);
+                }
+            }
+        }
+        self.scores = Some(scores);
+        self
+    }
+
+    /// 设置主题,使用其中的背景、字体与颜色
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// 默认的图片保存位置,在程序所在目录下(`{歌曲 ID}-card.png`)
+    pub fn output_path(&self) -> PathBuf {
+        LAUNCH_PATH.join(format!("{}-card.png", self.song.id))
+    }
+
+    /// 绘制并编码为指定格式
+    pub fn encode(&self, format: ImageOutputFormat) -> Result<Vec<u8>, ImageError> {
+        encode_image(self.render()?, format)
+    }
+
+    /// # 在内存中绘制图片
+    ///
+    /// 图片高度随难度数量变化,背景使用主题中的背景图片
+    pub fn render(&self) -> Result<RgbaImage, ImageError> {
+        let height = (HEADER_HEIGHT + ROW_HEIGHT * self.song.charts.len() as i32 + 36) as u32;
+        let background = image::open(self.pic_dir.join(&self.theme.background))?;
+        let mut img = background.resize_to_fill(CARD_WIDTH, height, FilterType::Lanczos3);
+
+        self.draw_header(&mut img)?;
+        for index in 0..self.song.charts.len() {
+            self.draw_chart_row(&mut img, index)?;
+        }
+
+        // 右下角的 Generated By
+        draw_text_mut(
+            &mut img,
+            Rgba(self.theme.colors.author),
+            CARD_WIDTH as i32 - 230,
+            height as i32 - 28,
+            Scale::uniform(15.0),
+            &get_font(&self.theme.fonts.text),
+            "Generated By Maimai-Search",
+        );
+        Ok(img.to_rgba8())
+    }
+
+    /// 绘制封面与歌曲基本信息
+    fn draw_header(&self, img: &mut DynamicImage) -> Result<(), ImageError> {
+        let font = get_font(&self.theme.fonts.text);
+        let cover = open_cover(&self.cover_dir, self.song.id as i32)?.resize_exact(
+            COVER_SIZE,
+            COVER_SIZE,
+            FilterType::Lanczos3,
+        );
+        self.draw_shadow(img, (MARGIN + 3, MARGIN + 3), (COVER_SIZE, COVER_SIZE));
+        overlay(img, &cover, MARGIN as i64, MARGIN as i64);
+
+        let left = MARGIN * 2 + COVER_SIZE as i32;
+        let max_width = CARD_WIDTH as i32 - left - MARGIN;
+        // 标题使用 30 号字,按照 16 号字换算截断宽度
+        let title = change_column_width(&self.song.title, max_width * 16 / 30, &font);
+        draw_text_mut(
+            img,
+            Rgba(self.theme.colors.name),
+            left,
+            MARGIN,
+            Scale::uniform(30.0),
+            &font,
+            &title,
+        );
+        let artist = change_column_width(&self.song.basic_info.artist, max_width * 16 / 20, &font);
+        draw_text_mut(
+            img,
+            Rgba(self.theme.colors.author),
+            left,
+            MARGIN + 42,
+            Scale::uniform(20.0),
+            &font,
+            &artist,
+        );
+
+        let info = &self.song.basic_info;
+        let mut lines = vec![
+            format!("ID: {}    类型: {}", self.song.id, self.song.song_type),
+            format!("分区: {}", info.genre),
+            format!("BPM: {}", info.bpm),
+            format!("版本: {}", info.from),
+        ];
+        // 早期的歌曲没有发布日期
+        if !info.release_date.is_empty() {
+            lines.push(format!("发布日期: {}", info.release_date));
+        }
+        for (index, line) in lines.iter().enumerate() {
+            draw_text_mut(
+                img,
+                Rgba(self.theme.colors.author),
+                left,
+                MARGIN + 84 + 26 * index as i32,
+                Scale::uniform(18.0),
+                &font,
+                line,
+            );
+        }
+        Ok(())
+    }
+
+    /// # 绘制单个难度
+    ///
+    /// 左侧为难度与定数,中间为谱师与 Note 数量,右侧为玩家成绩
+    fn draw_chart_row(&self, img: &mut DynamicImage, index: usize) -> Result<(), ImageError> {
+        let font = get_font(&self.theme.fonts.text);
+        let text_color = Rgba(self.theme.colors.text);
+        let level_label = LevelLabel::from_index(index).unwrap_or(LevelLabel::Basic);
+        let level_color = self.theme.level_color(level_label);
+        let chart = &self.song.charts[index];
+        let top = HEADER_HEIGHT + ROW_HEIGHT * index as i32;
+        let width = CARD_WIDTH - MARGIN as u32 * 2;
+
+        // 半透明底色与左侧的难度色条
+        self.draw_shadow(img, (MARGIN, top), (width, ROW_HEIGHT as u32 - 8));
+        let mut strip = RgbaImage::new(10, ROW_HEIGHT as u32 - 8);
+        draw_filled_rect_mut(
+            &mut strip,
+            Rect::at(0, 0).of_size(10, ROW_HEIGHT as u32 - 8),
+            level_color,
+        );
+        overlay(img, &strip, MARGIN as i64, top as i64);
+
+        let label = match self.song.is_utage() {
+            true => "U·TA·GE".to_string(),
+            false => level_label.to_string(),
+        };
+        draw_outlined_text(
+            img,
+            (level_color, Rgba([0, 0, 0, 255])),
+            (MARGIN + 22, top + 8),
+            Scale::uniform(22.0),
+            &font,
+            &label,
+        );
+        let level = self.song.level.get(index).cloned().unwrap_or_default();
+        let ds = self.song.ds.get(index).copied().unwrap_or_default();
+        draw_text_mut(
+            img,
+            text_color,
+            MARGIN + 22,
+            top + 42,
+            Scale::uniform(18.0),
+            &font,
+            &format!("Lv.{} ({:.1})", level, ds),
+        );
+
+        // 谱面信息
+        let left = MARGIN + 190;
+        draw_text_mut(
+            img,
+            text_color,
+            left,
+            top + 8,
+            Scale::uniform(16.0),
+            &font,
+            &change_column_width(
+                &format!("谱师: {}", chart.charter),
+                SCORE_LEFT - left,
+                &font,
+            ),
+        );
+        let names: &[&str] = match chart.notes.len() {
+            5 => &["TAP", "HOLD", "SLIDE", "TOUCH", "BREAK"],
+            _ => &["TAP", "HOLD", "SLIDE", "BREAK"],
+        };
+        let notes = names
+            .iter()
+            .zip(&chart.notes)
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect::<Vec<_>>()
+            .join(" / ");
+        draw_text_mut(
+            img,
+            text_color,
+            left,
+            top + 32,
+            Scale::uniform(13.0),
+            &font,
+            &notes,
+        );
+        draw_text_mut(
+            img,
+            text_color,
+            left,
+            top + 52,
+            Scale::uniform(13.0),
+            &font,
+            &format!(
+                "COMBO {}    DX 分数上限 {}",
+                chart.notes.iter().sum::<u32>(),
+                chart.max_dx_score()
+            ),
+        );
+
+        match self.scores.as_ref().map(|scores| scores.get(&level_label)) {
+            Some(Some(score)) => self.draw_score(img, score, top)?,
+            Some(None) => draw_text_mut(
+                img,
+                text_color,
+                SCORE_LEFT,
+                top + 26,
+                Scale::uniform(18.0),
+                &font,
+                "未游玩",
+            ),
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// 绘制玩家成绩:达成率、评级、FC/FS 与 Rating
+    fn draw_score(
+        &self,
+        img: &mut DynamicImage,
+        score: &ChartInfoResponse,
+        top: i32,
+    ) -> Result<(), ImageError> {
+        let font = get_font(&self.theme.fonts.text);
+        let text_color = Rgba(self.theme.colors.text);
+        draw_text_mut(
+            img,
+            text_color,
+            SCORE_LEFT,
+            top + 6,
+            Scale::uniform(22.0),
+            &font,
+            &format!("{:.4}%", score.achievements),
+        );
+        let rank_img = image::open(self.pic_dir.join(score.rate.get_file_name()))?;
+        overlay(
+            img,
+            &DrawBest::resize_pic(&rank_img, 0.3),
+            SCORE_LEFT as i64,
+            top as i64 + 38,
+        );
+        for (combo, x) in [(&score.fc, 650), (&score.fs, 682)] {
+            let icon = match combo.is_empty() {
+                true => "UI_MSS_MBase_Icon_Blank.png".to_string(),
+                false => format!("UI_MSS_MBase_Icon_{}_S.png", combo),
+            };
+            let icon_img = image::open(self.pic_dir.join(icon))?;
+            overlay(
+                img,
+                &DrawBest::resize_pic(&icon_img, 0.48),
+                x,
+                top as i64 + 38,
+            );
+        }
+        draw_text_mut(
+            img,
+            text_color,
+            718,
+            top + 44,
+            Scale::uniform(16.0),
+            &font,
+            &format!("Ra {}", score.ra),
+        );
+        Ok(())
+    }
+
+    /// 绘制半透明的阴影矩形,颜色与 B50 谱面元素的阴影相同
+    fn draw_shadow(&self, img: &mut DynamicImage, (x, y): (i32, i32), (width, height): (u32, u32)) {
+        let mut mask = RgbaImage::new(width, height);
+        draw_filled_rect_mut(
+            &mut mask,
+            Rect::at(0, 0).of_size(width, height),
+            Rgba(self.theme.colors.item_shadow),
+        );
+        overlay(img, &mask, x as i64, y as i64);
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use prettytable::{row, Cell, Row, Table};
+use serde::Serialize;
+
+use crate::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel, PUSH_RATES};
+use crate::service::chart_index::ChartIndex;
+use crate::service::level_sheet::best_scores;
+use crate::service::plate::PlateGoal;
+use crate::service::table::{MarkdownFormat, SongTable};
+
+/// 等级名称与该等级的全部谱面,未游玩的谱面成绩为空
+type LevelCharts = (String, Vec<Option<ChartInfoResponse>>);
+
+/// # 统计输出格式
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum StatsFormat {
+    /// 命令行表格
+    Table,
+    /// Markdown 表格
+    Md,
+    /// JSON 数据
+    Json,
+}
+
+/// 单个评级的谱面数量
+#[derive(Serialize)]
+pub struct RateCount {
+    pub rate: ChartRate,
+    pub count: usize,
+}
+
+/// # 单个等级的统计
+///
+/// FC、AP、FS、FDX 的数量都包含更高的等级,例如 AP 也计入 FC
+#[derive(Serialize)]
+pub struct LevelStats {
+    /// 等级,如 13+
+    pub level: String,
+    /// 已游玩的谱面数量
+    pub played: usize,
+    /// 本地数据库中的谱面数量
+    pub total: usize,
+    /// S ~ SSS+ 各评级的谱面数量,从高到低排列
+    pub rates: Vec<RateCount>,
+    /// 评级低于 S 的谱面数量
+    pub below_s: usize,
+    pub fc: usize,
+    pub ap: usize,
+    pub fs: usize,
+    pub fdx: usize,
+    /// 已游玩谱面的平均达成率,没有游玩时为空
+    pub average: Option<f32>,
+}
+
+/// # 等级完成度统计
+///
+/// 谱面总数来自本地数据库(不包含宴会场谱面),等级也以本地数据库为准,
+/// 所以每次 `update` 之后统计结果会跟着定数调整变化
+#[derive(Serialize)]
+pub struct LevelStatsReport {
+    pub nickname: String,
+    /// 按照等级从低到高排列
+    pub levels: Vec<LevelStats>,
+}
+
+impl LevelStatsReport {
+    pub fn new(index: &ChartIndex, scores: Vec<ChartInfoResponse>, nickname: &str) -> Self {
+        let mut best_scores = best_scores(scores);
+        let mut levels: BTreeMap<(u32, bool), LevelCharts> = BTreeMap::new();
+        for song in index.songs().filter(|song| !song.is_utage()) {
+            for (level_index, level) in song.level.iter().enumerate() {
+                let score = LevelLabel::from_index(level_index)
+                    .and_then(|level_label| best_scores.remove(&(song.id as i32, level_label)));
+                levels
+                    .entry(level_key(level))
+                    .or_insert_with(|| (level.clone(), Vec::new()))
+                    .1
+                    .push(score);
+            }
+        }
+        LevelStatsReport {
+            nickname: nickname.to_string(),
+            levels: levels
+                .into_values()
+                .map(|(level, charts)| LevelStats::new(level, &charts))
+                .collect(),
+        }
+    }
+
+    /// 输出为 JSON 字符串
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// 统计表格
+    pub fn table(&self) -> SongTable {
+        let mut table = Table::new();
+        let mut title = row!["等级", "已游玩", "谱面数"];
+        for rate in PUSH_RATES.iter().rev() {
+            title.add_cell(Cell::new(&rate.to_string()));
+        }
+        for name in ["S 以下", "FC", "AP", "FS", "FDX", "平均达成率"] {
+            title.add_cell(Cell::new(name));
+        }
+        table.set_titles(title);
+        for level in &self.levels {
+            let mut row = Row::empty();
+            row.add_cell(Cell::new(&level.level));
+            row.add_cell(Cell::new(&level.played.to_string()));
+            row.add_cell(Cell::new(&level.total.to_string()));
+            for count in level.rates.iter().map(|rate| rate.count).chain([
+                level.below_s,
+                level.fc,
+                level.ap,
+                level.fs,
+                level.fdx,
+            ]) {
+                row.add_cell(Cell::new(&count.to_string()));
+            }
+            row.add_cell(Cell::new(&match level.average {
+                Some(average) => format!("{:.4}%", average),
+                None => "-".to_string(),
+            }));
+            table.add_row(row);
+        }
+        SongTable {
+            info: format!("{} 的等级完成度", self.nickname),
+            table,
+            head: MarkdownFormat::H2,
+        }
+    }
+}
+
+impl LevelStats {
+    fn new(level: String, charts: &[Option<ChartInfoResponse>]) -> Self {
+        let scores: Vec<&ChartInfoResponse> = charts.iter().flatten().collect();
+        let count = |predicate: &dyn Fn(&ChartInfoResponse) -> bool| {
+            scores.iter().filter(|score| predicate(score)).count()
+        };
+        LevelStats {
+            level,
+            played: scores.len(),
+            total: charts.len(),
+            rates: PUSH_RATES
+                .iter()
+                .rev()
+                .map(|rate| RateCount {
+                    rate: *rate,
+                    count: count(&|score| ChartRate::from_achievement(score.achievements) == *rate),
+                })
+                .collect(),
+            below_s: count(&|score| score.achievements < ChartRate::S.min_achievement()),
+            fc: count(&|score| PlateGoal::Ji.is_cleared(score)),
+            ap: count(&|score| PlateGoal::Shen.is_cleared(score)),
+            fs: count(&|score| score.fs.starts_with("fs")),
+            fdx: count(&|score| PlateGoal::Wuwu.is_cleared(score)),
+            average: match scores.is_empty() {
+                true => None,
+                false => Some(
+                    scores.iter().map(|score| score.achievements).sum::<f32>()
+                        / scores.len() as f32,
+                ),
+            },
+        }
+    }
+}
+
+/// 等级的排序键,`13+` 排在 `13` 之后
+fn level_key(level: &str) -> (u32, bool) {
+    let plus = level.ends_with('+');
+    (
+        level.trim_end_matches('+').parse().unwrap_or_default(),
+        plus,
+    )
+}
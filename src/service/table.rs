@@ -214,13 +214,21 @@ impl TableService {
     /// 如果开启了本地化图片并且输出有值则会执行文件操作,图片信息经拼接得到例子如下:
     ///
     /// `![PANDORA PARADOXXX](https://www.diving-fish.com/covers/00834.png)`
-    fn get_song_picture(song: &Song, output: &Option<String>) -> String {
+    pub(crate) fn get_song_picture(song: &Song, output: &Option<String>) -> String {
+        format!(
+            "![{}]({})",
+            &song.title,
+            Self::get_song_picture_url(song, output)
+        )
+    }
+
+    /// # 获得图片地址
+    ///
+    /// 与 [`TableService::get_song_picture`] 相同,只返回地址部分,供 HTML 等其他格式使用
+    pub(crate) fn get_song_picture_url(song: &Song, output: &Option<String>) -> String {
         let config = &PROFILE.markdown.picture;
         if !config.local.enable || output.is_none() {
-            return format!(
-                "![{}]({}{:0>5}.png)",
-                &song.title, config.remote.prefix_url, &song.id
-            );
+            return format!("{}{:0>5}.png", config.remote.prefix_url, &song.id);
         }
 
         // 如果开启了本地化图片并且输出有值
@@ -255,15 +263,11 @@ impl TableService {
 
         if let Err(error) = copy_file(source_path, res_dir.join(&filename)) {
             error!("拷贝资源文件失败!使用远程地址\n[Cause]:{:?}", error);
-            return format!(
-                "![{}]({}{:0>5}.png)",
-                &song.title, config.remote.prefix_url, &song.id
-            );
+            return format!("{}{:0>5}.png", config.remote.prefix_url, &song.id);
         }
 
         format!(
-            "![{}]({}/{})",
-            &song.title,
+            "{}/{}",
             if *absolute {
                 res_dir.display().to_string()
             } else {
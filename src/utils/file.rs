@@ -1,12 +1,16 @@
+use std::collections::HashMap;
 use std::fs::{create_dir_all, File, OpenOptions};
 use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::sync::Mutex;
 use std::{fs, io};
 
-use log::error;
+use lazy_static::lazy_static;
+use log::{error, warn};
 use rusttype::Font;
 
 use crate::config::consts::{CONFIG_PATH, LAUNCH_PATH};
+use crate::config::theme::ThemeFonts;
 
 /// 如果路径存在则创建
 pub fn create_dir(path: &PathBuf) {
@@ -82,17 +86,43 @@ pub fn copy_file(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()>
     Ok(())
 }
 
-/// 获取微软雅黑字体
-pub fn get_msyh_font() -> Font<'static> {
-    let path = CONFIG_PATH.join("resource").join("msyh.ttc");
-    let font_data = fs::read(path).unwrap();
-    let font = Font::try_from_bytes(font_data.leak()).unwrap();
-    return font;
+lazy_static! {
+    /// 已经加载的字体,字体数据在整个程序运行期间都不会释放
+    static ref FONT_CACHE: Mutex<HashMap<String, Font<'static>>> = Mutex::new(HashMap::new());
 }
 
-/// 获取 Adobe 黑体字体
-pub fn get_adobe_simhei_font() -> Font<'static> {
-    let path = CONFIG_PATH.join("resource").join("adobe_simhei.otf");
-    let font_data = fs::read(path).unwrap();
-    Font::try_from_bytes(font_data.leak()).unwrap()
+/// # 获取字体
+///
+/// 相对路径基于`resource`目录,每个字体文件只会读取一次,之后从缓存中获取
+///
+/// 字体不存在或无法解析时使用默认主题的字体,默认字体也无法加载时退出
+pub fn get_font(file_name: &str) -> Font<'static> {
+    let mut cache = FONT_CACHE.lock().unwrap();
+    cache
+        .entry(file_name.to_string())
+        .or_insert_with(|| {
+            load_font(file_name).unwrap_or_else(|cause| {
+                warn!(
+                    "加载字体[{}]失败,使用默认字体\n[Cause]:{}",
+                    file_name, cause
+                );
+                let defaults = ThemeFonts::default();
+                [defaults.text, defaults.name]
+                    .iter()
+                    .filter(|default| default.as_str() != file_name)
+                    .find_map(|default| load_font(default).ok())
+                    .unwrap_or_else(|| {
+                        error!("默认字体也无法加载,请执行 update 命令更新资源文件");
+                        exit(exitcode::IOERR);
+                    })
+            })
+        })
+        .clone()
+}
+
+/// 读取并解析`resource`目录下的字体文件
+fn load_font(file_name: &str) -> Result<Font<'static>, String> {
+    let path = CONFIG_PATH.join("resource").join(file_name);
+    let font_data = fs::read(&path).map_err(|error| format!("{:?}", error))?;
+    Font::try_from_bytes(font_data.leak()).ok_or("字体文件格式错误".to_string())
 }
@@ -1,6 +1,9 @@
-use rusttype::{point, Scale};
+use std::io::Cursor;
 
-use crate::utils::file::get_adobe_simhei_font;
+use image::{DynamicImage, ImageError, ImageOutputFormat, RgbaImage};
+use rusttype::{point, Font, Scale};
+
+use crate::clients::user_data::entity::RatingRule;
 
 /// # 字符串全角转半角
 ///
@@ -25,31 +28,18 @@ pub fn string_to_half_width(input_string: &str) -> String {
     return String::from_utf16_lossy(&utf16_chars);
 }
 
-/// 获得 Rating 对应的姓名牌文件名
+/// 获得 Rating 对应的姓名牌文件名,颜色阈值由 `rule` 决定
 #[inline]
-pub fn get_ra_pic(rating: u32) -> String {
-    format!(
-        "UI_CMN_DXRating_S_{}.png",
-        match rating {
-            ra if ra < 1000 => "01",
-            ra if ra < 2000 => "02",
-            ra if ra < 4000 => "03",
-            ra if ra < 7000 => "04",
-            ra if ra < 10000 => "05",
-            ra if ra < 12000 => "06",
-            ra if ra < 13000 => "07",
-            ra if ra < 14500 => "08",
-            ra if ra < 15000 => "09",
-            _ => "10",
-        }
-    )
+pub fn get_ra_pic(rating: u32, rule: RatingRule) -> String {
+    format!("UI_CMN_DXRating_S_{:0>2}.png", rule.rating_color(rating))
 }
 
 /// # 截断过长的歌曲标题
-pub fn change_column_width(raw_title: &str, max_width: i32) -> String {
+///
+/// 按照 16 号字计算标题宽度,`font` 为绘制标题使用的字体
+pub fn change_column_width(raw_title: &str, max_width: i32, font: &Font) -> String {
     let mut title = String::new();
     for grapheme in raw_title.chars() {
-        let font = get_adobe_simhei_font();
         let glyphs: Vec<_> = font
             .layout(title.as_str(), Scale::uniform(16.0), point(0.0, 0.0))
             .collect();
@@ -65,3 +55,18 @@ pub fn change_column_width(raw_title: &str, max_width: i32) -> String {
     }
     title
 }
+
+/// # 编码图片
+///
+/// 返回编码后的字节,JPEG 不支持透明通道,编码前会转换为 RGB
+pub fn encode_image(image: RgbaImage, format: ImageOutputFormat) -> Result<Vec<u8>, ImageError> {
+    let image = DynamicImage::ImageRgba8(image);
+    let mut bytes = Cursor::new(Vec::new());
+    match format {
+        ImageOutputFormat::Jpeg(_) => {
+            DynamicImage::ImageRgb8(image.to_rgb8()).write_to(&mut bytes, format)?
+        }
+        _ => image.write_to(&mut bytes, format)?,
+    }
+    Ok(bytes.into_inner())
+}
@@ -0,0 +1,27 @@
+use maimai_search_lib::service::best_export::BestExport;
+use maimai_search_lib::service::maimai_best_50::BestList;
+
+mod common;
+
+/// # JSON 与 CSV 的写法一致
+///
+/// 难度、评级与 FC/FS 在所有格式中都使用显示名称
+#[test]
+fn json_matches_csv() {
+    let mut sd_best = BestList::new(35);
+    sd_best.push(common::pandora());
+    let export = BestExport::new(&sd_best, &BestList::new(15), "tester", "TESTER", None);
+
+    let json: serde_json::Value = serde_json::from_str(&export.to_json()).unwrap();
+    let chart = &json["sd"]["charts"][0];
+    let csv = export.to_csv();
+    let fields: Vec<&str> = csv.lines().nth(1).unwrap().split(',').collect();
+
+    assert_eq!(chart["level_label"], "MASTER");
+    assert_eq!(chart["level_label"], fields[4]);
+    assert_eq!(chart["rate"], "SSS+");
+    assert_eq!(chart["rate"], fields[7]);
+    assert_eq!(chart["fc"], "AP");
+    assert_eq!(chart["fc"], fields[8]);
+    assert_eq!(chart["fs"], fields[9]);
+}
@@ -0,0 +1,19 @@
+use maimai_search_lib::clients::user_data::entity::{ChartInfoResponse, LevelLabel};
+use maimai_search_lib::service::chart_index::ChartIndex;
+
+mod common;
+
+/// # 未游玩的谱面
+///
+/// 按照歌曲 ID 与难度匹配成绩,有成绩的谱面不会出现在结果中
+#[test]
+fn unplayed_charts() {
+    let index = ChartIndex::new(vec![common::song()]);
+    let played: Vec<ChartInfoResponse> = vec![common::pandora()];
+
+    let unplayed = index.unplayed(&played);
+    let labels: Vec<LevelLabel> = unplayed.iter().map(|chart| chart.level_label).collect();
+    assert!(labels == vec![LevelLabel::Basic, LevelLabel::Advanced, LevelLabel::Expert]);
+    assert_eq!(unplayed[2].ds, 12.5);
+    assert_eq!(index.unplayed(&[]).len(), 4);
+}
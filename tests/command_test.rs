@@ -37,3 +37,37 @@ fn name_console() -> Result<(), Box<dyn std::error::Error>> {
         .stdout(predicate::str::contains("初音ミクの消失"));
     Ok(())
 }
+
+/// # 单曲 Rating 计算
+///
+/// 在命令行中运行
+///
+/// ```shell
+/// maimai-search ra 14.6 100.5
+/// ```
+#[test]
+fn ra_console() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("ra").arg("14.6").arg("100.5");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Rating 为 328"));
+    Ok(())
+}
+
+/// # 目标 Rating 反查达成率
+///
+/// 在命令行中运行
+///
+/// ```shell
+/// maimai-search ra 13.0 --target 280
+/// ```
+#[test]
+fn ra_target_console() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME"))?;
+    cmd.arg("ra").arg("13.0").arg("--target").arg("280");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("100.0000% (SSS)"));
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! 测试与基准测试共用的数据,各个测试只覆盖自己关心的字段
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+use maimai_search_lib::clients::song_data::entity::Song;
+use maimai_search_lib::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel};
+use maimai_search_lib::service::maimai_best_50::BestList;
+
+const SONG: &str = r#"{"id": "834", "title": "PANDORA PARADOXXX", "type": "SD",
+    "ds": [6.0, 9.0, 12.5, 14.6], "level": ["6", "9", "12+", "14+"], "cids": [0, 1, 2, 3],
+    "charts": [{"notes": [1, 1, 1, 1], "charter": "-"}, {"notes": [1, 1, 1, 1], "charter": "-"},
+        {"notes": [1, 1, 1, 1], "charter": "-"}, {"notes": [1, 1, 1, 1], "charter": "-"}],
+    "basic_info": {"title": "PANDORA PARADOXXX", "artist": "削除", "genre": "maimai",
+        "bpm": 150, "release_date": "", "from": "maimai ORANGE", "is_new": false}}"#;
+
+/// 本地数据库中的 PANDORA PARADOXXX(ID 834),BASIC ~ MASTER 四个难度
+pub fn song() -> Song {
+    serde_json::from_str(SONG).unwrap()
+}
+
+/// PANDORA PARADOXXX MASTER 的 AP 成绩
+pub fn pandora() -> ChartInfoResponse {
+    ChartInfoResponse {
+        dx_score: 2000,
+        fc: "ap".to_string(),
+        level: "14+".to_string(),
+        ra: 328,
+        title: "PANDORA PARADOXXX".to_string(),
+        song_type: "SD".to_string(),
+        ..chart(834, 14.6, 100.5)
+    }
+}
+
+/// 只指定歌曲 ID、定数与达成率的 MASTER 成绩,评级由达成率换算
+pub fn chart(song_id: i32, ds: f32, achievements: f32) -> ChartInfoResponse {
+    ChartInfoResponse {
+        achievements,
+        ds,
+        dx_score: 0,
+        fc: String::new(),
+        fs: String::new(),
+        level: "14".to_string(),
+        level_label: LevelLabel::Master,
+        ra: 0,
+        rate: ChartRate::from_achievement(achievements),
+        song_id,
+        title: format!("Song {}", song_id),
+        song_type: "DX".to_string(),
+    }
+}
+
+/// 装满 AP+ 成绩的 Best 列表,歌曲 ID 从 `first_id` 开始,封面不存在的歌曲会使用默认封面
+pub fn best_list(size: usize, first_id: i32) -> BestList {
+    let mut best_list = BestList::new(size);
+    for index in 0..size {
+        best_list.push(ChartInfoResponse {
+            fc: "app".to_string(),
+            fs: "fsdp".to_string(),
+            ..chart(first_id + index as i32, 14.0, 100.5 - index as f32 * 0.1)
+        });
+    }
+    best_list
+}
+
+/// # 测试独占的临时目录
+///
+/// 目录名包含进程 ID 与测试名称,并行运行的测试之间不会互相覆盖文件,测试结束时需要自行删除
+pub fn temp_dir(test_name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("maimai-search-{}-{}", process::id(), test_name));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,54 @@
+use maimai_search_lib::service::judgement::{
+    parse_break_counts, parse_judge_counts, Judgement, JudgementCalculator, NoteCounts,
+};
+
+/// 标准谱面的 Note 数量:TAP 490,HOLD 10,SLIDE 67,BREAK 22
+const NOTES: [u32; 4] = [490, 10, 67, 22];
+
+/// # 由判定计算成绩
+///
+/// 基础分按照 Note 权重分配,绝赞分平均分配给全部 BREAK,达成率截断到 4 位小数
+#[test]
+fn judge_score() {
+    let notes = NoteCounts::from_notes(&NOTES).unwrap();
+    let calculator = JudgementCalculator::new(notes);
+
+    let result = calculator.score(&Judgement::default()).unwrap();
+    assert_eq!(result.achievements, 101.0);
+    assert_eq!(result.dx_score, 1767);
+
+    let judgement = Judgement {
+        tap: parse_judge_counts("5,2").unwrap(),
+        breaks: parse_break_counts("1,0,1").unwrap(),
+        ..Default::default()
+    };
+    let result = calculator.score(&judgement).unwrap();
+    assert_eq!(result.achievements, 100.7908);
+    assert_eq!(result.dx_score, 1767 - 5 - 4 - 1 - 2);
+
+    let judgement = Judgement {
+        breaks: parse_break_counts("0,0,0,0,23").unwrap(),
+        ..Default::default()
+    };
+    assert!(calculator.score(&judgement).is_err());
+}
+
+/// # 容错表
+///
+/// 数量不超过谱面中对应 Note 的数量,没有 TOUCH 的谱面不计算 TOUCH
+#[test]
+fn judge_tolerance() {
+    let calculator = JudgementCalculator::new(NoteCounts::from_notes(&NOTES).unwrap());
+    let tolerance = calculator.tolerance();
+    assert_eq!(tolerance.len(), 2);
+
+    let sss_plus = &tolerance[1];
+    assert_eq!(sss_plus.tap_great, 20);
+    assert_eq!(sss_plus.break_great, 3);
+    assert_eq!(sss_plus.break_perfect_high, 22);
+    assert!(sss_plus.touch_great.is_none());
+
+    assert!(NoteCounts::from_notes(&[1, 2, 3]).is_none());
+    assert!(parse_break_counts("1,2,3,4,5,6").is_err());
+    assert!(parse_judge_counts("1,2,3,4,5").is_err());
+}
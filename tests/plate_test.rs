@@ -0,0 +1,57 @@
+use maimai_search_lib::clients::user_data::entity::ChartInfoResponse;
+use maimai_search_lib::config::consts::CONFIG_PATH;
+use maimai_search_lib::service::plate::{plate_id, PlateGoal, PlateTarget};
+
+mod common;
+
+/// # 解析牌子名称
+///
+/// 第一个字为版本,其余为达成条件,舞系牌子包含全部旧框版本与 Re:MASTER
+#[test]
+fn parse_plate() {
+    let target = PlateTarget::parse("祭将").unwrap();
+    assert_eq!(target.goal, PlateGoal::Jiang);
+    assert_eq!(target.versions, vec!["maimai でらっくす FESTiVAL"]);
+    assert!(!target.remaster);
+
+    let target = PlateTarget::parse("舞舞舞").unwrap();
+    assert_eq!(target.goal, PlateGoal::Wuwu);
+    assert_eq!(target.versions.len(), 13);
+    assert!(target.remaster);
+
+    assert_eq!(PlateTarget::parse("熊极").unwrap().versions.len(), 2);
+    assert!(PlateTarget::parse("祭").is_err());
+    assert!(PlateTarget::parse("雷将").is_err());
+}
+
+/// # 牌子图片的 ID
+///
+/// 旧框牌子换算成资源包中的 `UI_Plate_XXXXXX.png`,安装了资源包时检查图片存在
+#[test]
+fn plate_image_id() {
+    assert_eq!(plate_id("舞舞舞"), Some(6151));
+    assert_eq!(plate_id("舞极"), plate_id("舞極"));
+    assert!(plate_id("真極").is_some());
+    assert!(plate_id("熊極").is_none());
+    assert!(plate_id("祭将").is_none());
+
+    let pic_dir = CONFIG_PATH.join("resource/mai/pic");
+    if !pic_dir.exists() {
+        return;
+    }
+    let id = plate_id("舞舞舞").unwrap();
+    assert!(pic_dir.join(format!("UI_Plate_{:0>6}.png", id)).exists());
+}
+
+/// # 达成条件
+#[test]
+fn plate_goal() {
+    let chart = ChartInfoResponse {
+        fs: "fsp".to_string(),
+        ..common::pandora()
+    };
+    assert!(PlateGoal::Ji.is_cleared(&chart));
+    assert!(PlateGoal::Jiang.is_cleared(&chart));
+    assert!(PlateGoal::Shen.is_cleared(&chart));
+    assert!(!PlateGoal::Wuwu.is_cleared(&chart));
+}
@@ -0,0 +1,43 @@
+use maimai_search_lib::clients::user_data::entity::PlayerData;
+use maimai_search_lib::clients::user_data::{read_player_data, save_player_data};
+
+mod common;
+
+/// # 读取 B50 数据与全部成绩
+///
+/// 两种格式写在同一个枚举里,需要按照字段区分出来
+#[test]
+fn read_both_formats() {
+    let chart = serde_json::to_string(&common::pandora()).unwrap();
+    let b50 = format!(
+        r#"{{"username": "tester", "nickname": "TESTER", "rating": 328, "additional_rating": 0,
+        "plate": "", "user_general_data": null, "charts": {{"sd": [{}], "dx": []}}}}"#,
+        chart
+    );
+    let records = format!(
+        r#"{{"username": "tester", "nickname": "TESTER", "rating": 328, "additional_rating": 0,
+        "plate": "", "records": [{}]}}"#,
+        chart
+    );
+    let dir = common::temp_dir("read_both_formats");
+    let (b50_path, records_path) = (dir.join("tester-b50.json"), dir.join("tester-records.json"));
+    std::fs::write(&b50_path, b50).unwrap();
+    std::fs::write(&records_path, records).unwrap();
+
+    match read_player_data(&b50_path).unwrap() {
+        PlayerData::B50(resp) => assert_eq!(resp.charts.sd[0].song_id, 834),
+        PlayerData::Records(_) => panic!("B50 数据被识别为全部成绩"),
+    }
+    let data = read_player_data(&records_path).unwrap();
+    assert!(matches!(&data, PlayerData::Records(resp) if resp.records.len() == 1));
+    assert_eq!(data.nickname(), "TESTER");
+    assert_eq!(data.clone().into_charts()[0].song_id, 834);
+
+    // 保存后再读取,格式保持不变
+    save_player_data(&data, &records_path).unwrap();
+    assert!(matches!(
+        read_player_data(&records_path).unwrap(),
+        PlayerData::Records(_)
+    ));
+    std::fs::remove_dir_all(dir).unwrap();
+}
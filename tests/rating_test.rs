@@ -0,0 +1,141 @@
+use maimai_search_lib::clients::user_data::entity::{
+    dx_star, ChartInfoResponse, ChartRate, RatingRule,
+};
+use maimai_search_lib::service::maimai_best_50::BestList;
+use maimai_search_lib::service::score_push::ScorePushService;
+
+mod common;
+
+/// # DX 规则(B25 + B15)
+///
+/// SSS+ 系数为 15.0,其余评级系数同样偏低,颜色阈值在 8500 封顶
+#[test]
+fn dx_rule() {
+    let rule = RatingRule::Dx;
+    assert_eq!(rule.compute_ra(14.0, 100.5), 211);
+    assert_eq!(rule.compute_ra(14.0, 100.0), 189);
+    assert_eq!(rule.compute_ra(14.0, 97.0), 169);
+    assert_eq!(rule.compute_ra(14.0, 55.0), 38);
+    assert_eq!(rule.best_sizes(), (25, 15));
+    assert_eq!(rule.rating_color(8000), 9);
+    assert_eq!(rule.rating_color(8500), 10);
+}
+
+/// # Splash 规则(B35 + B15)
+///
+/// 与查分器的计算结果保持一致,鸟加之后不再加分
+#[test]
+fn splash_rule() {
+    let rule = RatingRule::Splash;
+    assert_eq!(rule.compute_ra(14.6, 100.5), 328);
+    assert_eq!(rule.compute_ra(14.6, 101.0), 328);
+    assert_eq!(rule.compute_ra(13.0, 100.0), 280);
+    assert_eq!(rule.compute_ra(13.0, 99.9999), 274);
+    assert_eq!(rule.compute_ra(13.0, 55.0), 57);
+    assert_eq!(rule.compute_ra(13.0, 45.0), 40);
+    assert_eq!(rule.best_sizes(), (35, 15));
+    assert_eq!(rule.rating_color(14999), 9);
+    assert_eq!(rule.rating_color(15000), 10);
+}
+
+/// # PRiSM 规则
+///
+/// 阈值边缘(如 99.9999%)使用单独的系数,AP 额外加 1 分
+#[test]
+fn prism_rule() {
+    let rule = RatingRule::Prism;
+    assert_eq!(rule.compute_ra(14.6, 100.5), 328);
+    assert_eq!(rule.compute_ra(13.0, 99.9999), 278);
+    assert_eq!(rule.compute_ra(13.0, 96.9999), 221);
+    assert_eq!(rule.compute_ra(13.0, 45.0), 37);
+    assert_eq!(rule.ap_bonus(), 1);
+    assert_eq!(RatingRule::Splash.ap_bonus(), 0);
+}
+
+/// # 整数计算
+///
+/// 13.6 * 100.5% * 22.4 = 306.1632,浮点计算时容易出现精度问题,整数计算要保证结果稳定
+#[test]
+fn exact_compute() {
+    assert_eq!(RatingRule::Splash.compute_ra(13.6, 100.5), 306);
+    assert_eq!(RatingRule::Splash.compute_ra(12.5, 100.0), 270);
+}
+
+/// # 反查达成率
+///
+/// 反查得到的达成率一定能达到目标,并且再低 0.0001% 就达不到
+#[test]
+fn reverse_lookup() {
+    for rule in [RatingRule::Dx, RatingRule::Splash, RatingRule::Prism] {
+        let target = rule.compute_ra(13.7, 99.5);
+        let achievement = rule.min_achievement_for_ra(13.7, target).unwrap();
+        assert!(rule.compute_ra(13.7, achievement) >= target);
+        assert!(rule.compute_ra(13.7, achievement - 0.0001) < target);
+        assert_eq!(rule.min_achievement_for_ra(13.7, 1000), None);
+    }
+}
+
+/// # DX 分数星级
+///
+/// 理论值 1000 分时,850 / 900 / 930 / 950 / 970 分分别为一到五星
+#[test]
+fn dx_star_tiers() {
+    assert_eq!(dx_star(849, 1000), 0);
+    assert_eq!(dx_star(850, 1000), 1);
+    assert_eq!(dx_star(929, 1000), 2);
+    assert_eq!(dx_star(930, 1000), 3);
+    assert_eq!(dx_star(969, 1000), 4);
+    assert_eq!(dx_star(1000, 1000), 5);
+    assert_eq!(dx_star(100, 0), 0);
+}
+
+/// # 临近评级线的谱面
+///
+/// 只保留差距在范围内的谱面,按照 Rating 收益排序,SSS+ 的谱面没有下一个评级
+#[test]
+fn near_threshold() {
+    let charts = vec![
+        common::chart(1, 14.0, 100.3),
+        common::chart(2, 14.0, 99.9),
+        common::chart(3, 14.0, 99.0),
+        common::chart(4, 14.0, 100.5),
+    ];
+    let items = ScorePushService::near_threshold(&charts, 0.2, RatingRule::Splash);
+    let ids: Vec<i32> = items.iter().map(|item| item.song_id).collect();
+    assert_eq!(ids, vec![1, 2]);
+    assert!(items[0].target_rate == ChartRate::SSSP);
+    assert!(items[1].target_rate == ChartRate::SSS);
+    assert!(items[0].gain() > items[1].gain());
+
+    // PRiSM 规则下 AP 谱面的 Rating 与 B50 中显示的一致
+    let ap = ChartInfoResponse {
+        fc: "ap".to_string(),
+        ..common::chart(5, 14.0, 100.3)
+    };
+    let items = ScorePushService::near_threshold(std::slice::from_ref(&ap), 0.2, RatingRule::Prism);
+    assert_eq!(items[0].current_ra, RatingRule::Prism.chart_ra(&ap));
+    assert_eq!(
+        items[0].target_ra,
+        RatingRule::Prism.compute_ra(14.0, 100.5) + 1
+    );
+}
+
+/// # PRiSM 规则下 AP 谱面的推分收益
+///
+/// 当前 Rating 与目标 Rating 都包含 AP 奖励,收益只来自达成率的提升
+#[test]
+fn prism_push_gain() {
+    let rule = RatingRule::Prism;
+    let mut sd_best = BestList::with_rule(35, rule);
+    sd_best.push(ChartInfoResponse {
+        fc: "ap".to_string(),
+        ..common::chart(1, 14.0, 100.3)
+    });
+    let items = ScorePushService::get_push_list(&sd_best, &BestList::with_rule(15, rule), &[], 10);
+    assert_eq!(items[0].current_ra, rule.compute_ra(14.0, 100.3) + 1);
+    assert_eq!(items[0].target_ra, rule.compute_ra(14.0, 100.5) + 1);
+    assert_eq!(
+        items[0].gain,
+        rule.compute_ra(14.0, 100.5) - rule.compute_ra(14.0, 100.3)
+    );
+}
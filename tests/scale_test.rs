@@ -0,0 +1,34 @@
+use image::GenericImageView;
+use maimai_search_lib::config::consts::CONFIG_PATH;
+use maimai_search_lib::service::maimai_best_50::DrawBest;
+
+mod common;
+
+/// # 高倍率绘制
+///
+/// 2 倍绘制的图片是 1 倍的两倍大小,Rating 姓名牌直接按照最终大小缩放,也是两倍大小;需要安装资源包
+#[test]
+fn double_scale() {
+    if !CONFIG_PATH.join("resource/mai/pic").exists() {
+        eprintln!("没有安装资源包,跳过绘制测试");
+        return;
+    }
+    let draw = |scale: f32| {
+        let mut draw_best = DrawBest::new(
+            common::best_list(35, 1),
+            common::best_list(15, 1001),
+            "TESTER",
+        )
+        .with_scale(scale);
+        let frame = draw_best.rating_frame().unwrap();
+        (frame.dimensions(), draw_best.render().unwrap().dimensions())
+    };
+    let ((frame_width, frame_height), (width, height)) = draw(1.0);
+    let ((double_frame_width, double_frame_height), (double_width, double_height)) = draw(2.0);
+
+    assert_eq!((double_width, double_height), (width * 2, height * 2));
+    // 保持宽高比缩放时高度向下取整,宽度会有几个像素的误差
+    let ratio = |double: u32, single: u32| double as f32 / single as f32;
+    assert!((ratio(double_frame_width, frame_width) - 2.0).abs() < 0.03);
+    assert!((ratio(double_frame_height, frame_height) - 2.0).abs() < 0.03);
+}
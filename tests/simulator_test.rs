@@ -0,0 +1,65 @@
+use maimai_search_lib::clients::user_data::entity::{ChartInfoResponse, ChartRate, LevelLabel};
+use maimai_search_lib::service::chart_index::ChartIndex;
+use maimai_search_lib::service::simulator::{parse_simulated_chart, SimulateService};
+
+mod common;
+
+/// # 解析假设的成绩
+///
+/// 难度支持名称与下标,Re:MASTER 可以带冒号
+#[test]
+fn parse_chart() {
+    let chart = parse_simulated_chart("834:master:100.5").unwrap();
+    assert_eq!(chart.song_id, 834);
+    assert!(chart.level_label == LevelLabel::Master);
+    assert_eq!(chart.achievements, 100.5);
+
+    assert!(parse_simulated_chart("834:2:99%").unwrap().level_label == LevelLabel::Expert);
+    assert!(
+        parse_simulated_chart("834:Re:MASTER:99")
+            .unwrap()
+            .level_label
+            == LevelLabel::ReMaster
+    );
+    assert!(parse_simulated_chart("834:master").is_err());
+    assert!(parse_simulated_chart("834:9:100").is_err());
+    assert!(parse_simulated_chart("834:master:102").is_err());
+}
+
+/// # 叠加假设的成绩
+///
+/// 只替换更高的成绩,没有成绩的谱面从本地数据库补全
+#[test]
+fn apply_simulated() {
+    let index = ChartIndex::new(vec![common::song()]);
+    let mut charts = vec![ChartInfoResponse {
+        fc: "fc".to_string(),
+        ..common::chart(834, 14.6, 99.5)
+    }];
+
+    let simulated = [
+        parse_simulated_chart("834:master:100.5").unwrap(),
+        parse_simulated_chart("834:expert:98").unwrap(),
+    ];
+    SimulateService::apply(&mut charts, &index, &simulated).unwrap();
+    assert_eq!(charts.len(), 2);
+    assert_eq!(charts[0].achievements, 100.5);
+    assert!(charts[0].rate == ChartRate::SSSP);
+    assert_eq!(charts[0].fc, "fc");
+    assert_eq!(charts[1].ds, 12.5);
+    assert_eq!(charts[1].level, "12+");
+
+    SimulateService::apply(
+        &mut charts,
+        &index,
+        &[parse_simulated_chart("834:master:99").unwrap()],
+    )
+    .unwrap();
+    assert_eq!(charts[0].achievements, 100.5);
+    assert!(SimulateService::apply(
+        &mut charts,
+        &index,
+        &[parse_simulated_chart("835:0:99").unwrap()]
+    )
+    .is_err());
+}
@@ -0,0 +1,31 @@
+use maimai_search_lib::clients::user_data::entity::LevelLabel;
+use maimai_search_lib::config::theme::Theme;
+
+mod common;
+
+/// # 读取部分字段的主题文件
+///
+/// 主题文件中省略的字段使用默认主题的值
+#[test]
+fn partial_theme() {
+    let dir = common::temp_dir("partial_theme");
+    let path = dir.join("theme.yml");
+    std::fs::write(
+        &path,
+        "background: custom.png\ngrid:\n  item_width: 150\ncolors:\n  text: [0, 0, 0, 255]\n",
+    )
+    .unwrap();
+    let theme = Theme::load(Some(&path));
+    let default = Theme::default();
+
+    assert_eq!(theme.background, "custom.png");
+    assert_eq!(theme.grid.item_width, 150);
+    assert_eq!(theme.grid.item_height, default.grid.item_height);
+    assert_eq!(theme.colors.text, [0, 0, 0, 255]);
+    assert_eq!(
+        theme.level_color(LevelLabel::Master),
+        LevelLabel::Master.label_color()
+    );
+    assert_eq!(theme.logo.file, default.logo.file);
+    std::fs::remove_dir_all(dir).unwrap();
+}